@@ -0,0 +1,24 @@
+use std::str::FromStr;
+
+// Value of the first `name=value` argument, `name` including its leading dashes and `=`
+pub fn option<T: FromStr>(args: &[String], name: &str) -> anyhow::Result<Option<T>>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match args.iter().find_map(|arg| arg.strip_prefix(name)) {
+        Some(value) => Ok(Some(value.parse::<T>()?)),
+        None => Ok(None),
+    }
+}
+
+pub fn flag(args: &[String], name: &str) -> bool {
+    args.iter().any(|arg| arg == name)
+}
+
+// Arguments that are not options, the command name included
+pub fn positionals(args: &[String]) -> Vec<&str> {
+    args.iter()
+        .filter(|arg| !arg.starts_with("--"))
+        .map(String::as_str)
+        .collect()
+}
@@ -105,6 +105,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn should_overlapping() {
         assert_eq!(
             SectionAssigmentPair {
@@ -1,316 +1,903 @@
-use anyhow::anyhow;
-use std::{
-    collections::HashMap,
-    fs::File,
-    hash::Hash,
-    io::{BufRead, BufReader},
-    str::FromStr,
-};
-
-use itertools::Itertools;
-
-#[derive(Debug, PartialEq, Clone, Hash, Eq)]
-struct Crate {
-    name: char,
-}
-
-impl FromStr for Crate {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if !(s.len() == 3 || s.len() == 4) {
-            return Err(anyhow!("Invalid crate format: {}", s));
-        }
-
-        let mut chars = s.chars();
-        match (
-            chars.next(),
-            chars.next(),
-            chars.next(),
-            chars.next(),
-            chars.next(),
-        ) {
-            (Some('['), Some(c), Some(']'), None, None)
-            | (Some('['), Some(c), Some(']'), Some(' '), None) => Ok(Crate { name: c }),
-            _ => Err(anyhow!("Invalid crate : {}", s)),
-        }
-    }
-}
-
-fn parse_crate_row(row: &str) -> Vec<Option<Crate>> {
-    row.chars()
-        .collect::<Vec<char>>()
-        .chunks(4)
-        .map(|c| match c.len() {
-            3 => c.iter().collect::<String>().parse::<Crate>().ok(),
-            4 => c.iter().collect::<String>().parse::<Crate>().ok(),
-            _ => None,
-        })
-        .collect::<Vec<Option<Crate>>>()
-}
-
-#[derive(Debug, Clone)]
-struct Stack {
-    crates: Vec<Crate>,
-}
-
-impl Stack {
-    fn new() -> Stack {
-        Stack { crates: vec![] }
-    }
-
-    fn add(&mut self, crate_: &Crate) {
-        self.crates.push(crate_.to_owned());
-    }
-
-    fn remove(&mut self) -> Option<Crate> {
-        self.crates.pop()
-    }
-}
-
-enum CraneType {
-    CrateMover9000,
-    CrateMover9001,
-}
-
-#[derive(Debug, Clone)]
-struct Cargo {
-    stacks: HashMap<usize, Stack>,
-}
-
-impl Cargo {
-    fn apply_serie(&mut self, serie: &Serie, crane_type: CraneType) -> anyhow::Result<()> {
-        let origin = self
-            .stacks
-            .get_mut(&serie.origin)
-            .ok_or_else(|| anyhow!("Invalid serie: origin stack not found"))?;
-
-        let mut to_add = (0..serie.quantity).fold(vec![], |acc: Vec<Crate>, _| {
-            let mut acc = acc;
-            if let Some(crate_) = origin.remove() {
-                acc.push(crate_);
-            }
-
-            acc
-        });
-
-        let destination = self
-            .stacks
-            .get_mut(&serie.destination)
-            .ok_or_else(|| anyhow!("Invalid serie: destination stack not found"))?;
-
-        match crane_type {
-            CraneType::CrateMover9000 => {
-                for crate_ in to_add {
-                    destination.add(&crate_);
-                }
-            }
-            CraneType::CrateMover9001 => loop {
-                let crate_ = to_add.pop();
-                match crate_ {
-                    Some(crate_) => destination.add(&crate_),
-                    None => break,
-                }
-            },
-        }
-
-        Ok(())
-    }
-
-    fn get_crate_in_top(&self) -> String {
-        self.stacks.keys().sorted().fold(String::new(), |acc, key| {
-            let mut acc = acc;
-            if let Some(crate_) = self.stacks.get(key).unwrap().crates.last() {
-                acc.push(crate_.name);
-            }
-
-            acc
-        })
-    }
-}
-
-#[derive(Debug, Clone)]
-struct Serie {
-    quantity: u32,
-    origin: usize,
-    destination: usize,
-}
-
-// parse from string with format "move quantity from origin to destination"
-impl FromStr for Serie {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts = s.split(' ').collect::<Vec<&str>>();
-        if parts.len() != 6 {
-            return Err(anyhow!("Invalid serie format: {}", s));
-        }
-        let quantity = parts[1].parse::<u32>()?;
-        let origin = parts[3].parse::<usize>()?;
-        let destination = parts[5].parse::<usize>()?;
-
-        Ok(Serie {
-            quantity,
-            origin,
-            destination,
-        })
-    }
-}
-
-fn load_cargo(lines: &[String]) -> anyhow::Result<Cargo> {
-    let mut stacks = HashMap::new();
-    let (stacks_line, supplies_lines) = lines
-        .split_last()
-        .ok_or_else(|| anyhow!("Invalid cargo format"))?;
-    stacks_line.chars().for_each(|c| {
-        if let Ok(c) = c.to_string().parse::<usize>() {
-            stacks.insert(c, Stack::new());
-        }
-    });
-
-    let mut supplies_lines = supplies_lines.to_vec();
-    supplies_lines.reverse();
-    supplies_lines.iter().for_each(|line| {
-        let crates = parse_crate_row(line);
-        crates.iter().enumerate().for_each(|(i, c)| {
-            if let Some(c) = c {
-                let key = i + 1;
-                if let Ok(stack) = stacks
-                    .get_mut(&key)
-                    .ok_or_else(|| anyhow!("Invalid cargo format"))
-                {
-                    stack.add(c);
-                }
-            }
-        });
-    });
-
-    Ok(Cargo { stacks })
-}
-
-fn load_from_file(path: &str) -> anyhow::Result<(Cargo, Vec<Serie>)> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    let mut cargo_lines = vec![];
-    let mut series: Vec<Serie> = vec![];
-
-    for line in reader.lines() {
-        let line = line?;
-        if line.is_empty() {
-            continue;
-        }
-
-        let serie = line.parse::<Serie>();
-        match serie {
-            Ok(serie) => series.push(serie),
-            Err(_) => cargo_lines.push(line),
-        }
-    }
-
-    let cargo = load_cargo(&cargo_lines)?;
-
-    Ok((cargo, series))
-}
-
-pub fn day05() -> anyhow::Result<()> {
-    let (mut cargo, series) = load_from_file("data/day05.txt")?;
-
-    let mut cargo_part2 = cargo.clone();
-    let series_part2 = series.clone();
-
-    for serie in series {
-        cargo.apply_serie(&serie, CraneType::CrateMover9000)?;
-    }
-
-    println!("Day05 part1: {}", cargo.get_crate_in_top());
-
-    for serie in series_part2 {
-        cargo_part2.apply_serie(&serie, CraneType::CrateMover9001)?;
-    }
-    println!("Day05 part1: {}", cargo_part2.get_crate_in_top());
-
-    Ok(())
-}
-
-#[cfg(test)]
-mod tests {
-
-    use super::*;
-
-    #[test]
-    fn should_parse_crate() {
-        assert_eq!("[A]".parse::<Crate>().unwrap().name, 'A');
-        assert_eq!("[A] ".parse::<Crate>().unwrap(), Crate { name: 'A' });
-    }
-
-    #[test]
-    fn should_parse_crate_row_with_empty_and_crate() {
-        let resut = parse_crate_row("    [D]");
-        assert_eq!(resut.len(), 2);
-        assert_eq!(resut[0], None);
-        assert_eq!(resut[1], Some(Crate { name: 'D' }));
-    }
-
-    #[test]
-    fn should_parse_crate_row_with_two_crates() {
-        let resut = parse_crate_row("[N] [C]");
-        assert_eq!(resut.len(), 2);
-        assert_eq!(resut[0], Some(Crate { name: 'N' }));
-        assert_eq!(resut[1], Some(Crate { name: 'C' }));
-    }
-
-    #[test]
-    fn should_parse_crate_row_with_three_crates() {
-        let resut = parse_crate_row("[Z] [M] [P]");
-        assert_eq!(resut.len(), 3);
-        assert_eq!(resut[0], Some(Crate { name: 'Z' }));
-        assert_eq!(resut[1], Some(Crate { name: 'M' }));
-        assert_eq!(resut[2], Some(Crate { name: 'P' }));
-    }
-
-    #[test]
-    fn should_parse_crate_row_with_one_crate() {
-        let resut = parse_crate_row("[L]");
-        assert_eq!(resut.len(), 1);
-        assert_eq!(resut[0], Some(Crate { name: 'L' }));
-    }
-
-    #[test]
-    fn should_parse_series() {
-        let serie = "move 1 from 2 to 1".parse::<Serie>().unwrap();
-        assert_eq!(serie.quantity, 1);
-        assert_eq!(serie.origin, 2);
-        assert_eq!(serie.destination, 1);
-    }
-
-    #[test]
-    fn should_get_crate_in_top() {
-        let mut stacks = HashMap::new();
-        stacks.insert(
-            1,
-            Stack {
-                crates: vec![Crate { name: 'C' }],
-            },
-        );
-        stacks.insert(
-            2,
-            Stack {
-                crates: vec![Crate { name: 'M' }],
-            },
-        );
-        stacks.insert(
-            3,
-            Stack {
-                crates: vec![
-                    Crate { name: 'P' },
-                    Crate { name: 'D' },
-                    Crate { name: 'N' },
-                    Crate { name: 'Z' },
-                ],
-            },
-        );
-        let cargo = Cargo { stacks };
-
-        assert_eq!(cargo.get_crate_in_top(), "CMZ");
-    }
-}
+use anyhow::anyhow;
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::File,
+    hash::Hash,
+    io::{BufRead, BufReader},
+    str::FromStr,
+    time::Duration,
+};
+
+use itertools::Itertools;
+
+use crate::cli::{flag, option};
+use crate::validation::{LineNumber, Mode, Warning};
+
+mod crane;
+mod journal;
+mod planner;
+
+use crane::{parse_crane, Crane, CrateMover9000, CrateMover9001};
+use journal::Journal;
+use planner::{plan, Budget, Plan};
+
+#[derive(Debug, PartialEq, Clone, Hash, Eq)]
+struct Crate {
+    name: char,
+}
+
+impl FromStr for Crate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !(s.len() == 3 || s.len() == 4) {
+            return Err(anyhow!("Invalid crate format: {}", s));
+        }
+
+        let mut chars = s.chars();
+        match (
+            chars.next(),
+            chars.next(),
+            chars.next(),
+            chars.next(),
+            chars.next(),
+        ) {
+            (Some('['), Some(c), Some(']'), None, None)
+            | (Some('['), Some(c), Some(']'), Some(' '), None) => Ok(Crate { name: c }),
+            _ => Err(anyhow!("Invalid crate : {}", s)),
+        }
+    }
+}
+
+// Stack number of the footer line, with the columns it spans in the drawing
+#[derive(Debug, PartialEq, Clone)]
+struct StackLabel {
+    key: usize,
+    start: usize,
+    end: usize,
+}
+
+impl StackLabel {
+    fn contains(&self, column: usize) -> bool {
+        column >= self.start && column < self.end
+    }
+}
+
+fn parse_stack_labels(line: &str) -> anyhow::Result<Vec<StackLabel>> {
+    let mut labels: Vec<StackLabel> = vec![];
+    let mut current: Option<(usize, String)> = None;
+
+    for (column, c) in line.chars().chain(std::iter::once(' ')).enumerate() {
+        match (c.is_whitespace(), current.take()) {
+            (false, None) => current = Some((column, c.to_string())),
+            (false, Some((start, mut label))) => {
+                label.push(c);
+                current = Some((start, label));
+            }
+            (true, Some((start, label))) => {
+                let key = label.parse::<usize>().map_err(|_| {
+                    anyhow!("Invalid stack label '{}' at column {}", label, start + 1)
+                })?;
+                if labels.iter().any(|l| l.key == key) {
+                    return Err(anyhow!(
+                        "Duplicate stack label '{}' at column {}",
+                        label,
+                        start + 1
+                    ));
+                }
+                labels.push(StackLabel {
+                    key,
+                    start,
+                    end: column,
+                });
+            }
+            (true, None) => {}
+        }
+    }
+
+    if labels.is_empty() {
+        return Err(anyhow!("Invalid cargo format: no stack label found"));
+    }
+
+    Ok(labels)
+}
+
+// Crates are matched to the stack label found under their name, so labels can be any width
+fn parse_crate_row(row: &str, labels: &[StackLabel]) -> anyhow::Result<Vec<Option<Crate>>> {
+    let chars = row.chars().collect::<Vec<char>>();
+    let mut crates: Vec<Option<Crate>> = vec![None; labels.len()];
+    let mut column = 0;
+
+    while column < chars.len() {
+        if chars[column].is_whitespace() {
+            column += 1;
+            continue;
+        }
+
+        let cell = chars[column..(column + 3).min(chars.len())]
+            .iter()
+            .collect::<String>();
+        let crate_ = cell
+            .parse::<Crate>()
+            .map_err(|_| anyhow!("Invalid crate '{}' at column {}", cell, column + 1))?;
+        let index = labels
+            .iter()
+            .position(|label| label.contains(column + 1))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Crate {} at column {} is not aligned with any stack label",
+                    cell,
+                    column + 1
+                )
+            })?;
+        if crates[index].is_some() {
+            return Err(anyhow!(
+                "Two crates found above stack {} at column {}",
+                labels[index].key,
+                column + 1
+            ));
+        }
+
+        crates[index] = Some(crate_);
+        column += 3;
+    }
+
+    Ok(crates)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Stack {
+    crates: Vec<Crate>,
+}
+
+impl Stack {
+    fn new() -> Stack {
+        Stack { crates: vec![] }
+    }
+
+    fn add(&mut self, crate_: &Crate) {
+        self.crates.push(crate_.to_owned());
+    }
+
+    fn remove(&mut self) -> Option<Crate> {
+        self.crates.pop()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Usage {
+    lifts: usize,
+    cost: u64,
+}
+
+impl std::ops::AddAssign for Usage {
+    fn add_assign(&mut self, other: Self) {
+        self.lifts += other.lifts;
+        self.cost += other.cost;
+    }
+}
+
+type Procedure = Vec<(LineNumber, Serie)>;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Cargo {
+    stacks: HashMap<usize, Stack>,
+}
+
+impl Cargo {
+    fn check_serie(&self, serie: &Serie) -> anyhow::Result<()> {
+        let origin = self
+            .stacks
+            .get(&serie.origin)
+            .ok_or_else(|| anyhow!("{}: origin stack not found", serie))?;
+        if !self.stacks.contains_key(&serie.destination) {
+            return Err(anyhow!("{}: destination stack not found", serie));
+        }
+        if origin.crates.len() < serie.quantity as usize {
+            return Err(anyhow!(
+                "{}: stack {} only has {} crates",
+                serie,
+                serie.origin,
+                origin.crates.len()
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn apply_serie(&mut self, serie: &Serie, crane: &dyn Crane) -> anyhow::Result<Usage> {
+        if !self.stacks.contains_key(&serie.destination) {
+            return Err(anyhow!("Invalid serie: destination stack not found"));
+        }
+
+        let origin = self
+            .stacks
+            .get_mut(&serie.origin)
+            .ok_or_else(|| anyhow!("Invalid serie: origin stack not found"))?;
+
+        let mut picked = (0..serie.quantity)
+            .filter_map(|_| origin.remove())
+            .collect::<Vec<Crate>>();
+        picked.reverse();
+
+        let destination = self
+            .stacks
+            .get_mut(&serie.destination)
+            .ok_or_else(|| anyhow!("Invalid serie: destination stack not found"))?;
+
+        let mut usage = Usage::default();
+        for lift in crane.lift(picked) {
+            usage += Usage {
+                lifts: 1,
+                cost: crane.cost(&lift),
+            };
+            for crate_ in lift.iter() {
+                destination.add(crate_);
+            }
+        }
+
+        Ok(usage)
+    }
+
+    // Reports the problem of the `index`th move if it has one, and tells whether its stacks
+    // exist so that it can be applied
+    fn validate_serie(
+        &self,
+        index: usize,
+        line: LineNumber,
+        serie: &Serie,
+        mode: Mode,
+        warnings: &mut Vec<Warning>,
+    ) -> anyhow::Result<bool> {
+        if let Err(e) = self.check_serie(serie) {
+            let warning = Warning {
+                line,
+                message: format!("move {}, {}", index + 1, e),
+            };
+            mode.report(warning, warnings)?;
+        }
+
+        Ok(self.stacks.contains_key(&serie.origin) && self.stacks.contains_key(&serie.destination))
+    }
+
+    // Underflowing moves only move the crates available when they are accepted
+    fn run_procedure(
+        &mut self,
+        series: &[(LineNumber, Serie)],
+        crane: &dyn Crane,
+        mode: Mode,
+    ) -> anyhow::Result<(Usage, Vec<Warning>)> {
+        let mut usage = Usage::default();
+        let mut warnings = vec![];
+        for (index, (line, serie)) in series.iter().enumerate() {
+            if self.validate_serie(index, *line, serie, mode, &mut warnings)? {
+                usage += self.apply_serie(serie, crane)?;
+            }
+        }
+
+        Ok((usage, warnings))
+    }
+
+    fn get_crate_in_top(&self) -> String {
+        self.stacks.keys().sorted().fold(String::new(), |acc, key| {
+            let mut acc = acc;
+            if let Some(crate_) = self.stacks.get(key).unwrap().crates.last() {
+                acc.push(crate_.name);
+            }
+
+            acc
+        })
+    }
+}
+
+// Print the cargo in the puzzle drawing format, stack numbers footer included
+impl fmt::Display for Cargo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keys = self.stacks.keys().sorted().collect::<Vec<&usize>>();
+        let widths = keys
+            .iter()
+            .map(|key| key.to_string().len().max(3))
+            .collect::<Vec<usize>>();
+        let height = self
+            .stacks
+            .values()
+            .map(|stack| stack.crates.len())
+            .max()
+            .unwrap_or(0);
+
+        for level in (0..height).rev() {
+            let row = keys
+                .iter()
+                .zip(widths.iter())
+                .map(|(key, width)| match self.stacks[key].crates.get(level) {
+                    Some(crate_) => format!("{:^width$}", format!("[{}]", crate_.name)),
+                    None => " ".repeat(*width),
+                })
+                .join(" ");
+            writeln!(f, "{}", row.trim_end())?;
+        }
+
+        let footer = keys
+            .iter()
+            .zip(widths.iter())
+            .map(|(key, width)| format!("{:^width$}", key))
+            .join(" ");
+        write!(f, "{}", footer.trim_end())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Serie {
+    quantity: u32,
+    origin: usize,
+    destination: usize,
+}
+
+// parse from string with format "move quantity from origin to destination"
+impl FromStr for Serie {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s.split(' ').collect::<Vec<&str>>();
+        if parts.len() != 6 || parts[0] != "move" || parts[2] != "from" || parts[4] != "to" {
+            return Err(anyhow!("Invalid serie format: {}", s));
+        }
+        let quantity = parts[1].parse::<u32>()?;
+        let origin = parts[3].parse::<usize>()?;
+        let destination = parts[5].parse::<usize>()?;
+
+        Ok(Serie {
+            quantity,
+            origin,
+            destination,
+        })
+    }
+}
+
+impl fmt::Display for Serie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.quantity, self.origin, self.destination
+        )
+    }
+}
+
+fn load_cargo(lines: &[String]) -> anyhow::Result<Cargo> {
+    let (stacks_line, supplies_lines) = lines
+        .split_last()
+        .ok_or_else(|| anyhow!("Invalid cargo format"))?;
+    let labels =
+        parse_stack_labels(stacks_line).map_err(|e| anyhow!("Line {}: {}", lines.len(), e))?;
+    let mut stacks = labels
+        .iter()
+        .map(|label| (label.key, Stack::new()))
+        .collect::<HashMap<usize, Stack>>();
+
+    for (index, line) in supplies_lines.iter().enumerate().rev() {
+        let crates =
+            parse_crate_row(line, &labels).map_err(|e| anyhow!("Line {}: {}", index + 1, e))?;
+        for (label, crate_) in labels.iter().zip(crates) {
+            if let (Some(crate_), Some(stack)) = (crate_, stacks.get_mut(&label.key)) {
+                stack.add(&crate_);
+            }
+        }
+    }
+
+    Ok(Cargo { stacks })
+}
+
+fn load_procedure(
+    lines: &[String],
+    first_line: LineNumber,
+    mode: Mode,
+) -> anyhow::Result<(Procedure, Vec<Warning>)> {
+    let mut series = vec![];
+    let mut warnings = vec![];
+
+    for (index, line) in lines.iter().enumerate() {
+        let line_number = first_line + index;
+        if line.is_empty() {
+            continue;
+        }
+
+        match line.parse::<Serie>() {
+            Ok(serie) => series.push((line_number, serie)),
+            Err(_) => {
+                let warning = Warning {
+                    line: line_number,
+                    message: format!("invalid move '{}'", line),
+                };
+                mode.report(warning, &mut warnings)?;
+            }
+        }
+    }
+
+    Ok((series, warnings))
+}
+
+// The drawing and the rearrangement procedure are separated by the first blank line
+fn load_puzzle(lines: &[String], mode: Mode) -> anyhow::Result<(Cargo, Procedure, Vec<Warning>)> {
+    let separator = lines
+        .iter()
+        .position(|line| line.is_empty())
+        .unwrap_or(lines.len());
+    let cargo = load_cargo(&lines[..separator])?;
+    let procedure = lines.get(separator + 1..).unwrap_or(&[]);
+    let (series, warnings) = load_procedure(procedure, separator + 2, mode)?;
+
+    Ok((cargo, series, warnings))
+}
+
+fn load_from_file(path: &str, mode: Mode) -> anyhow::Result<(Cargo, Procedure, Vec<Warning>)> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let lines = reader.lines().collect::<Result<Vec<String>, _>>()?;
+
+    load_puzzle(&lines, mode)
+}
+
+fn print_warnings(warnings: &[Warning]) {
+    for warning in warnings {
+        println!("Day05 warning, {}", warning);
+    }
+}
+
+fn run(cargo: &Cargo, series: &Procedure, crane: &dyn Crane, mode: Mode) -> anyhow::Result<String> {
+    let mut cargo = cargo.clone();
+    let (usage, warnings) = cargo.run_procedure(series, crane, mode)?;
+    print_warnings(&warnings);
+
+    Ok(format!(
+        "{} ({} lifts, cost {})",
+        cargo.get_crate_in_top(),
+        usage.lifts,
+        usage.cost
+    ))
+}
+
+fn solve(path: &str, crane: Option<&dyn Crane>, mode: Mode) -> anyhow::Result<()> {
+    let (cargo, series, warnings) = load_from_file(path, mode)?;
+    print_warnings(&warnings);
+
+    match crane {
+        Some(crane) => println!("Day05: {}", run(&cargo, &series, crane, mode)?),
+        None => {
+            println!(
+                "Day05 part1: {}",
+                run(&cargo, &series, &CrateMover9000, mode)?
+            );
+            println!(
+                "Day05 part2: {}",
+                run(&cargo, &series, &CrateMover9001, mode)?
+            );
+        }
+    }
+
+    Ok(())
+}
+
+pub fn day05() -> anyhow::Result<()> {
+    solve("data/day05.txt", None, Mode::Strict)
+}
+
+// Step through the procedure applied with the given crane from an interactive prompt
+fn replay(path: &str, crane: &dyn Crane, mode: Mode) -> anyhow::Result<()> {
+    let (cargo, series, warnings) = load_from_file(path, mode)?;
+    print_warnings(&warnings);
+
+    let mut journal = Journal::new(cargo);
+    print_warnings(&journal.record(&series, crane, mode)?);
+
+    journal::replay(
+        &mut journal,
+        std::io::stdin().lock(),
+        &mut std::io::stdout(),
+    )
+}
+
+// Only the drawing of the file is used, the procedure is ignored when there is one
+fn load_drawing(path: &str) -> anyhow::Result<Cargo> {
+    let lines = BufReader::new(File::open(path)?)
+        .lines()
+        .take_while(|line| !matches!(line, Ok(line) if line.is_empty()))
+        .collect::<Result<Vec<String>, _>>()?;
+
+    load_cargo(&lines)
+}
+
+fn print_plan(start: &str, goal: &str, crane: &dyn Crane, budget: Budget) -> anyhow::Result<()> {
+    match plan(&load_drawing(start)?, &load_drawing(goal)?, crane, budget) {
+        Plan::Found(series) => {
+            for serie in series {
+                println!("{}", serie);
+            }
+        }
+        Plan::Unreachable => println!("Day05 plan: goal is unreachable"),
+        Plan::BudgetExceeded { explored } => println!(
+            "Day05 plan: budget exceeded after exploring {} states",
+            explored
+        ),
+    }
+
+    Ok(())
+}
+
+// Usage: day05 [replay] [--lenient] [--crane=9000|9001|capacity:N|rotating|metered:N] [path]
+//        day05 plan [--crane=...] [--max-nodes=N] [--max-seconds=N] <start> <goal>
+pub fn cli(args: &[String]) -> anyhow::Result<()> {
+    let mode = match flag(args, "--lenient") {
+        true => Mode::Lenient,
+        false => Mode::Strict,
+    };
+    let crane = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--crane="))
+        .map(parse_crane)
+        .transpose()?;
+    let paths = args
+        .iter()
+        .skip_while(|arg| matches!(arg.as_str(), "replay" | "plan"))
+        .filter(|arg| !arg.starts_with("--"))
+        .map(String::as_str)
+        .collect::<Vec<&str>>();
+    let path = paths.first().copied().unwrap_or("data/day05.txt");
+
+    match args.first().map(String::as_str) {
+        Some("plan") => match paths[..] {
+            [start, goal] => print_plan(
+                start,
+                goal,
+                crane.as_deref().unwrap_or(&CrateMover9000),
+                Budget {
+                    max_nodes: option(args, "--max-nodes=")?.unwrap_or(1_000_000),
+                    max_duration: Duration::from_secs(
+                        option(args, "--max-seconds=")?.unwrap_or(60),
+                    ),
+                },
+            ),
+            _ => Err(anyhow!("Usage: day05 plan <start> <goal>")),
+        },
+        Some("replay") => replay(path, crane.as_deref().unwrap_or(&CrateMover9000), mode),
+        _ => solve(path, crane.as_deref(), mode),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::random::Random;
+
+    #[test]
+    fn should_parse_crate() {
+        assert_eq!("[A]".parse::<Crate>().unwrap().name, 'A');
+        assert_eq!("[A] ".parse::<Crate>().unwrap(), Crate { name: 'A' });
+    }
+
+    fn labels(count: usize) -> Vec<StackLabel> {
+        parse_stack_labels(&(1..=count).map(|key| format!(" {} ", key)).join(" ")).unwrap()
+    }
+
+    #[test]
+    fn should_parse_stack_labels() {
+        assert_eq!(
+            parse_stack_labels(" 1   2  10 ").unwrap(),
+            vec![
+                StackLabel {
+                    key: 1,
+                    start: 1,
+                    end: 2
+                },
+                StackLabel {
+                    key: 2,
+                    start: 5,
+                    end: 6
+                },
+                StackLabel {
+                    key: 10,
+                    start: 8,
+                    end: 10
+                },
+            ]
+        );
+        assert!(parse_stack_labels(" 1   a").is_err());
+        assert!(parse_stack_labels(" 1   1").is_err());
+        assert!(parse_stack_labels("   ").is_err());
+    }
+
+    #[test]
+    fn should_parse_crate_row_with_empty_and_crate() {
+        let resut = parse_crate_row("    [D]", &labels(2)).unwrap();
+        assert_eq!(resut.len(), 2);
+        assert_eq!(resut[0], None);
+        assert_eq!(resut[1], Some(Crate { name: 'D' }));
+    }
+
+    #[test]
+    fn should_parse_crate_row_with_two_crates() {
+        let resut = parse_crate_row("[N] [C]", &labels(2)).unwrap();
+        assert_eq!(resut.len(), 2);
+        assert_eq!(resut[0], Some(Crate { name: 'N' }));
+        assert_eq!(resut[1], Some(Crate { name: 'C' }));
+    }
+
+    #[test]
+    fn should_parse_crate_row_with_three_crates() {
+        let resut = parse_crate_row("[Z] [M] [P]", &labels(3)).unwrap();
+        assert_eq!(resut.len(), 3);
+        assert_eq!(resut[0], Some(Crate { name: 'Z' }));
+        assert_eq!(resut[1], Some(Crate { name: 'M' }));
+        assert_eq!(resut[2], Some(Crate { name: 'P' }));
+    }
+
+    #[test]
+    fn should_parse_crate_row_with_one_crate() {
+        let resut = parse_crate_row("[L]", &labels(1)).unwrap();
+        assert_eq!(resut.len(), 1);
+        assert_eq!(resut[0], Some(Crate { name: 'L' }));
+    }
+
+    #[test]
+    fn should_reject_crate_row_not_aligned_with_labels() {
+        assert!(parse_crate_row("  [D]", &labels(2)).is_err());
+        assert!(parse_crate_row("[A] [B] [C]", &labels(2)).is_err());
+        assert!(parse_crate_row("[A [B]", &labels(2)).is_err());
+    }
+
+    #[test]
+    fn should_load_cargo_with_ten_stacks_and_more() {
+        let lines = vec![
+            "                                    [K]".to_string(),
+            "[A]                                 [J] [L]".to_string(),
+            " 1   2   3   4   5   6   7   8   9  10  11".to_string(),
+        ];
+        let cargo = load_cargo(&lines).unwrap();
+        assert_eq!(cargo.stacks.len(), 11);
+        assert_eq!(
+            cargo.stacks[&10].crates,
+            vec![Crate { name: 'J' }, Crate { name: 'K' }]
+        );
+        assert_eq!(cargo.get_crate_in_top(), "AKL");
+    }
+
+    #[test]
+    fn should_report_line_of_misaligned_row() {
+        let lines = vec![
+            "  [D]".to_string(),
+            "[N] [C]".to_string(),
+            " 1   2".to_string(),
+        ];
+        let error = load_cargo(&lines).unwrap_err().to_string();
+        assert!(error.starts_with("Line 1:"), "{}", error);
+    }
+
+    fn sample_puzzle(procedure: &[&str]) -> Vec<String> {
+        let mut lines = sample_lines();
+        lines.push(String::new());
+        lines.extend(procedure.iter().map(|l| l.to_string()));
+        lines
+    }
+
+    #[test]
+    fn should_run_sample_procedure() {
+        let lines = sample_puzzle(&[
+            "move 1 from 2 to 1",
+            "move 3 from 1 to 3",
+            "move 2 from 2 to 1",
+            "move 1 from 1 to 2",
+        ]);
+        let (cargo, series, warnings) = load_puzzle(&lines, Mode::Strict).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(
+            series.iter().map(|(l, _)| *l).collect::<Vec<_>>(),
+            vec![6, 7, 8, 9]
+        );
+
+        let mut part1 = cargo.clone();
+        let (usage, _) = part1
+            .run_procedure(&series, &CrateMover9000, Mode::Strict)
+            .unwrap();
+        assert_eq!(part1.get_crate_in_top(), "CMZ");
+        assert_eq!(usage, Usage { lifts: 7, cost: 0 });
+
+        let mut part2 = cargo;
+        let (usage, _) = part2
+            .run_procedure(&series, &CrateMover9001, Mode::Strict)
+            .unwrap();
+        assert_eq!(part2.get_crate_in_top(), "MCD");
+        assert_eq!(usage, Usage { lifts: 4, cost: 0 });
+    }
+
+    #[test]
+    fn should_report_usage_of_custom_cranes() {
+        let lines = sample_puzzle(&[
+            "move 1 from 2 to 1",
+            "move 3 from 1 to 3",
+            "move 2 from 2 to 1",
+            "move 1 from 1 to 2",
+        ]);
+        let (cargo, series, _) = load_puzzle(&lines, Mode::Strict).unwrap();
+
+        let report = run(
+            &cargo,
+            &series,
+            parse_crane("capacity:2").unwrap().as_ref(),
+            Mode::Strict,
+        );
+        assert_eq!(report.unwrap(), "MCZ (5 lifts, cost 0)");
+        let report = run(
+            &cargo,
+            &series,
+            parse_crane("rotating").unwrap().as_ref(),
+            Mode::Strict,
+        );
+        assert_eq!(report.unwrap(), "CMZ (4 lifts, cost 0)");
+        let report = run(
+            &cargo,
+            &series,
+            parse_crane("metered:3").unwrap().as_ref(),
+            Mode::Strict,
+        );
+        assert_eq!(report.unwrap(), "MCD (4 lifts, cost 21)");
+    }
+
+    #[test]
+    fn strict_mode_should_reject_invalid_move_line() {
+        let lines = sample_puzzle(&["move 1 from 2 to 1", "mvoe 3 from 1 to 3"]);
+        let error = load_puzzle(&lines, Mode::Strict).unwrap_err().to_string();
+        assert_eq!(error, "line 7: invalid move 'mvoe 3 from 1 to 3'");
+    }
+
+    #[test]
+    fn lenient_mode_should_skip_invalid_move_line() {
+        let lines = sample_puzzle(&["move 1 from 2 to 1", "move three from 1 to 3"]);
+        let (cargo, series, warnings) = load_puzzle(&lines, Mode::Lenient).unwrap();
+        assert_eq!(cargo, load_cargo(&sample_lines()).unwrap());
+        assert_eq!(series.len(), 1);
+        assert_eq!(
+            warnings,
+            vec![Warning {
+                line: 7,
+                message: "invalid move 'move three from 1 to 3'".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn strict_mode_should_reject_underflowing_move() {
+        let lines = sample_puzzle(&["move 1 from 2 to 1", "move 4 from 1 to 3"]);
+        let (mut cargo, series, _) = load_puzzle(&lines, Mode::Strict).unwrap();
+        let before = cargo.clone();
+        let error = cargo
+            .run_procedure(&series, &CrateMover9000, Mode::Strict)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "line 7: move 2, move 4 from 1 to 3: stack 1 only has 3 crates"
+        );
+        assert_ne!(cargo, before);
+        assert_eq!(cargo.stacks[&1].crates.len(), 3);
+    }
+
+    #[test]
+    fn lenient_mode_should_move_available_crates_on_underflow() {
+        let lines = sample_puzzle(&["move 5 from 1 to 3", "move 1 from 4 to 3"]);
+        let (mut cargo, series, _) = load_puzzle(&lines, Mode::Lenient).unwrap();
+        let (_, warnings) = cargo
+            .run_procedure(&series, &CrateMover9000, Mode::Lenient)
+            .unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].line, 6);
+        assert_eq!(warnings[1].line, 7);
+        assert!(cargo.stacks[&1].crates.is_empty());
+        assert_eq!(cargo.stacks[&3].crates.len(), 3);
+    }
+
+    #[test]
+    fn should_not_move_crates_to_missing_stack() {
+        let mut cargo = load_cargo(&sample_lines()).unwrap();
+        let before = cargo.clone();
+        let serie = "move 1 from 1 to 9".parse::<Serie>().unwrap();
+        assert!(cargo.apply_serie(&serie, &CrateMover9000).is_err());
+        assert_eq!(cargo, before);
+    }
+
+    #[test]
+    fn should_parse_series() {
+        let serie = "move 1 from 2 to 1".parse::<Serie>().unwrap();
+        assert_eq!(serie.quantity, 1);
+        assert_eq!(serie.origin, 2);
+        assert_eq!(serie.destination, 1);
+        assert_eq!(serie.to_string(), "move 1 from 2 to 1");
+        assert!("take 1 from 2 to 1".parse::<Serie>().is_err());
+    }
+
+    #[test]
+    fn should_get_crate_in_top() {
+        let mut stacks = HashMap::new();
+        stacks.insert(
+            1,
+            Stack {
+                crates: vec![Crate { name: 'C' }],
+            },
+        );
+        stacks.insert(
+            2,
+            Stack {
+                crates: vec![Crate { name: 'M' }],
+            },
+        );
+        stacks.insert(
+            3,
+            Stack {
+                crates: vec![
+                    Crate { name: 'P' },
+                    Crate { name: 'D' },
+                    Crate { name: 'N' },
+                    Crate { name: 'Z' },
+                ],
+            },
+        );
+        let cargo = Cargo { stacks };
+
+        assert_eq!(cargo.get_crate_in_top(), "CMZ");
+    }
+
+    fn sample_lines() -> Vec<String> {
+        vec![
+            "    [D]".to_string(),
+            "[N] [C]".to_string(),
+            "[Z] [M] [P]".to_string(),
+            " 1   2   3".to_string(),
+        ]
+    }
+
+    fn to_lines(cargo: &Cargo) -> Vec<String> {
+        cargo.to_string().lines().map(|l| l.to_string()).collect()
+    }
+
+    fn random_cargo(random: &mut Random, max_stacks: usize) -> Cargo {
+        let stacks = (1..=random.next(max_stacks as u64) as usize + 1)
+            .map(|key| {
+                let crates = (0..random.next(12))
+                    .map(|_| Crate {
+                        name: (b'A' + random.next(26) as u8) as char,
+                    })
+                    .collect();
+                (key, Stack { crates })
+            })
+            .collect();
+
+        Cargo { stacks }
+    }
+
+    #[test]
+    fn should_print_cargo_in_puzzle_format() {
+        let cargo = load_cargo(&sample_lines()).unwrap();
+        assert_eq!(to_lines(&cargo), sample_lines());
+    }
+
+    #[test]
+    fn should_print_empty_stacks() {
+        let mut stacks = HashMap::new();
+        stacks.insert(1, Stack::new());
+        stacks.insert(2, Stack::new());
+        assert_eq!(Cargo { stacks }.to_string(), " 1   2");
+    }
+
+    #[test]
+    fn parse_print_parse_should_round_trip() {
+        let mut random = Random(0x2022_0005);
+        for _ in 0..500 {
+            let cargo = random_cargo(&mut random, 120);
+            let printed = to_lines(&cargo);
+            let parsed = load_cargo(&printed).unwrap();
+            assert_eq!(parsed, cargo, "round trip failed for:\n{}", cargo);
+            assert_eq!(to_lines(&parsed), printed);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use anyhow::anyhow;
+
+use super::Crate;
+
+// A crane receives the crates taken from the origin stack, from bottom to top, and splits
+// them into lifts. Each lift lists its crates in the order they land on the destination.
+pub(super) trait Crane {
+    fn lift(&self, crates: Vec<Crate>) -> Vec<Vec<Crate>>;
+
+    fn cost(&self, _lift: &[Crate]) -> u64 {
+        0
+    }
+}
+
+// Moves one crate at a time
+pub(super) struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn lift(&self, crates: Vec<Crate>) -> Vec<Vec<Crate>> {
+        crates
+            .into_iter()
+            .rev()
+            .map(|crate_| vec![crate_])
+            .collect()
+    }
+}
+
+// Moves all the crates at once, keeping their order
+pub(super) struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn lift(&self, crates: Vec<Crate>) -> Vec<Vec<Crate>> {
+        match crates.is_empty() {
+            true => vec![],
+            false => vec![crates],
+        }
+    }
+}
+
+// Moves the crates by batches of at most `capacity` crates, starting from the top
+pub(super) struct CapacityCrane {
+    capacity: usize,
+}
+
+impl Crane for CapacityCrane {
+    fn lift(&self, crates: Vec<Crate>) -> Vec<Vec<Crate>> {
+        crates
+            .rchunks(self.capacity)
+            .map(|lift| lift.to_vec())
+            .collect()
+    }
+}
+
+// Moves all the crates at once and turns the group upside down
+pub(super) struct RotatingCrane;
+
+impl Crane for RotatingCrane {
+    fn lift(&self, crates: Vec<Crate>) -> Vec<Vec<Crate>> {
+        CrateMover9001.lift(crates.into_iter().rev().collect())
+    }
+}
+
+// Moves like a CrateMover 9001 but charges for every crate moved
+pub(super) struct MeteredCrane {
+    cost_per_crate: u64,
+}
+
+impl Crane for MeteredCrane {
+    fn lift(&self, crates: Vec<Crate>) -> Vec<Vec<Crate>> {
+        CrateMover9001.lift(crates)
+    }
+
+    fn cost(&self, lift: &[Crate]) -> u64 {
+        self.cost_per_crate * lift.len() as u64
+    }
+}
+
+// Parse from string with format "9000", "9001", "capacity:N", "rotating" or "metered:N"
+pub(super) fn parse_crane(s: &str) -> anyhow::Result<Box<dyn Crane>> {
+    let mut parts = s.split(':');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some("9000"), None, None) => Ok(Box::new(CrateMover9000)),
+        (Some("9001"), None, None) => Ok(Box::new(CrateMover9001)),
+        (Some("rotating"), None, None) => Ok(Box::new(RotatingCrane)),
+        (Some("capacity"), Some(capacity), None) => match capacity.parse::<usize>()? {
+            0 => Err(anyhow!("Invalid crane capacity: {}", capacity)),
+            capacity => Ok(Box::new(CapacityCrane { capacity })),
+        },
+        (Some("metered"), Some(cost), None) => Ok(Box::new(MeteredCrane {
+            cost_per_crate: cost.parse::<u64>()?,
+        })),
+        _ => Err(anyhow!("Unknown crane: {}", s)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn crates(names: &str) -> Vec<Crate> {
+        names.chars().map(|name| Crate { name }).collect()
+    }
+
+    fn names(lifts: Vec<Vec<Crate>>) -> Vec<String> {
+        lifts
+            .iter()
+            .map(|lift| lift.iter().map(|c| c.name).collect())
+            .collect()
+    }
+
+    #[test]
+    fn should_split_crates_into_lifts() {
+        assert_eq!(names(CrateMover9000.lift(crates("ABC"))), ["C", "B", "A"]);
+        assert_eq!(names(CrateMover9001.lift(crates("ABC"))), ["ABC"]);
+        assert_eq!(
+            names(CapacityCrane { capacity: 2 }.lift(crates("ABCDE"))),
+            ["DE", "BC", "A"]
+        );
+        assert_eq!(names(RotatingCrane.lift(crates("ABC"))), ["CBA"]);
+        assert!(CrateMover9001.lift(vec![]).is_empty());
+    }
+
+    #[test]
+    fn metered_crane_should_charge_per_crate() {
+        let crane = MeteredCrane { cost_per_crate: 3 };
+        assert_eq!(crane.cost(&crates("ABCD")), 12);
+        assert_eq!(CrateMover9000.cost(&crates("ABCD")), 0);
+    }
+
+    #[test]
+    fn should_parse_crane() {
+        assert!(parse_crane("9000").is_ok());
+        assert!(parse_crane("capacity:3").is_ok());
+        assert!(parse_crane("metered:2").is_ok());
+        assert!(parse_crane("capacity:0").is_err());
+        assert!(parse_crane("capacity").is_err());
+        assert!(parse_crane("9002").is_err());
+    }
+}
@@ -0,0 +1,385 @@
+use anyhow::anyhow;
+use itertools::Itertools;
+use std::{
+    fmt,
+    io::{BufRead, Write},
+};
+
+use crate::validation::{LineNumber, Mode, Warning};
+
+use super::{Cargo, Crane, Crate, Serie};
+
+// A move as it was applied: the crates lifted from the top of the origin stack and the
+// crates they became on top of the destination stack, both from bottom to top
+#[derive(Debug, Clone)]
+struct Entry {
+    serie: Serie,
+    lifted: Vec<Crate>,
+    placed: Vec<Crate>,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Journal {
+    cargo: Cargo,
+    entries: Vec<Entry>,
+    position: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub(super) struct StackDiff {
+    key: usize,
+    from: Vec<Crate>,
+    to: Vec<Crate>,
+}
+
+impl fmt::Display for StackDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = |crates: &[Crate]| crates.iter().map(|c| c.name).collect::<String>();
+        write!(
+            f,
+            "stack {}: {} -> {}",
+            self.key,
+            names(&self.from),
+            names(&self.to)
+        )
+    }
+}
+
+impl Journal {
+    pub(super) fn new(cargo: Cargo) -> Self {
+        Self {
+            cargo,
+            entries: vec![],
+            position: 0,
+        }
+    }
+
+    fn top_crates(&self, key: usize, count: usize) -> Vec<Crate> {
+        match self.cargo.stacks.get(&key) {
+            Some(stack) => {
+                stack.crates[stack.crates.len() - count.min(stack.crates.len())..].to_vec()
+            }
+            None => vec![],
+        }
+    }
+
+    fn replace_top(&mut self, key: usize, count: usize, crates: &[Crate]) {
+        if let Some(stack) = self.cargo.stacks.get_mut(&key) {
+            stack.crates.truncate(stack.crates.len() - count);
+            stack.crates.extend_from_slice(crates);
+        }
+    }
+
+    // Applying a move after some undo drops the moves that could have been redone
+    pub(super) fn apply(&mut self, serie: &Serie, crane: &dyn Crane) -> anyhow::Result<()> {
+        let lifted = self.top_crates(serie.origin, serie.quantity as usize);
+        self.cargo.apply_serie(serie, crane)?;
+        let placed = self.top_crates(serie.destination, lifted.len());
+
+        self.entries.truncate(self.position);
+        self.entries.push(Entry {
+            serie: serie.clone(),
+            lifted,
+            placed,
+        });
+        self.position += 1;
+
+        Ok(())
+    }
+
+    // Applies a procedure checked as `Cargo::run_procedure` does it
+    pub(super) fn record(
+        &mut self,
+        series: &[(LineNumber, Serie)],
+        crane: &dyn Crane,
+        mode: Mode,
+    ) -> anyhow::Result<Vec<Warning>> {
+        let mut warnings = vec![];
+        for (index, (line, serie)) in series.iter().enumerate() {
+            if self
+                .cargo
+                .validate_serie(index, *line, serie, mode, &mut warnings)?
+            {
+                self.apply(serie, crane)?;
+            }
+        }
+
+        Ok(warnings)
+    }
+
+    pub(super) fn undo(&mut self) -> Option<Serie> {
+        let entry = self.entries.get(self.position.checked_sub(1)?)?.clone();
+        self.replace_top(entry.serie.destination, entry.placed.len(), &[]);
+        self.replace_top(entry.serie.origin, 0, &entry.lifted);
+        self.position -= 1;
+
+        Some(entry.serie)
+    }
+
+    pub(super) fn redo(&mut self) -> Option<Serie> {
+        let entry = self.entries.get(self.position)?.clone();
+        self.replace_top(entry.serie.origin, entry.lifted.len(), &[]);
+        self.replace_top(entry.serie.destination, 0, &entry.placed);
+        self.position += 1;
+
+        Some(entry.serie)
+    }
+
+    pub(super) fn jump_to(&mut self, position: usize) -> anyhow::Result<()> {
+        if position > self.entries.len() {
+            return Err(anyhow!(
+                "Invalid position {}: the journal has {} moves",
+                position,
+                self.entries.len()
+            ));
+        }
+
+        while self.position > position {
+            self.undo();
+        }
+        while self.position < position {
+            self.redo();
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn diff(&self, from: usize, to: usize) -> anyhow::Result<Vec<StackDiff>> {
+        let mut journal = self.clone();
+        journal.jump_to(from)?;
+        let before = journal.cargo.clone();
+        journal.jump_to(to)?;
+
+        Ok(before
+            .stacks
+            .keys()
+            .sorted()
+            .filter_map(|key| {
+                let from = &before.stacks[key].crates;
+                let to = &journal.cargo.stacks[key].crates;
+                (from != to).then(|| StackDiff {
+                    key: *key,
+                    from: from.clone(),
+                    to: to.clone(),
+                })
+            })
+            .collect())
+    }
+}
+
+fn parse_position(argument: Option<&str>) -> anyhow::Result<usize> {
+    argument
+        .ok_or_else(|| anyhow!("Missing move number"))?
+        .parse::<usize>()
+        .map_err(|e| anyhow!("Invalid move number: {}", e))
+}
+
+fn run_command<W: Write>(journal: &mut Journal, line: &str, output: &mut W) -> anyhow::Result<()> {
+    let mut parts = line.split_whitespace();
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some("show"), None, None) => writeln!(output, "{}", journal.cargo)?,
+        (Some("top"), None, None) => writeln!(output, "{}", journal.cargo.get_crate_in_top())?,
+        (Some("undo"), None, None) => match journal.undo() {
+            Some(serie) => writeln!(output, "undone: {}", serie)?,
+            None => writeln!(output, "nothing to undo")?,
+        },
+        (Some("redo"), None, None) => match journal.redo() {
+            Some(serie) => writeln!(output, "redone: {}", serie)?,
+            None => writeln!(output, "nothing to redo")?,
+        },
+        (Some("goto"), position, None) => journal.jump_to(parse_position(position)?)?,
+        (Some("diff"), from, to) => {
+            for diff in journal.diff(parse_position(from)?, parse_position(to)?)? {
+                writeln!(output, "{}", diff)?;
+            }
+        }
+        _ => writeln!(
+            output,
+            "commands: show, top, undo, redo, goto <n>, diff <from> <to>, quit"
+        )?,
+    }
+
+    Ok(())
+}
+
+pub(super) fn replay<R: BufRead, W: Write>(
+    journal: &mut Journal,
+    input: R,
+    output: &mut W,
+) -> anyhow::Result<()> {
+    writeln!(
+        output,
+        "move {}/{}",
+        journal.position,
+        journal.entries.len()
+    )?;
+    for line in input.lines() {
+        let line = line?;
+        if matches!(line.trim(), "quit" | "exit") {
+            break;
+        }
+
+        if let Err(e) = run_command(journal, &line, output) {
+            writeln!(output, "error: {}", e)?;
+        }
+        writeln!(
+            output,
+            "move {}/{}",
+            journal.position,
+            journal.entries.len()
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::{load_cargo, CrateMover9000, CrateMover9001};
+    use super::*;
+
+    fn sample_journal(crane: &dyn Crane) -> (Cargo, Journal) {
+        let cargo = load_cargo(&[
+            "    [D]".to_string(),
+            "[N] [C]".to_string(),
+            "[Z] [M] [P]".to_string(),
+            " 1   2   3".to_string(),
+        ])
+        .unwrap();
+        let mut journal = Journal::new(cargo.clone());
+        for serie in [
+            "move 1 from 2 to 1",
+            "move 3 from 1 to 3",
+            "move 2 from 2 to 1",
+            "move 1 from 1 to 2",
+        ] {
+            journal
+                .apply(&serie.parse::<Serie>().unwrap(), crane)
+                .unwrap();
+        }
+
+        (cargo, journal)
+    }
+
+    #[test]
+    fn undo_should_restore_every_state() {
+        let cranes: [&dyn Crane; 2] = [&CrateMover9000, &CrateMover9001];
+        for crane in cranes {
+            let (initial, mut journal) = sample_journal(crane);
+            let mut states = vec![journal.cargo.clone()];
+            while journal.undo().is_some() {
+                states.push(journal.cargo.clone());
+            }
+
+            assert_eq!(journal.cargo, initial);
+            assert_eq!(states.len(), 5);
+            while journal.redo().is_some() {
+                states.pop();
+                assert_eq!(Some(&journal.cargo), states.last());
+            }
+        }
+    }
+
+    #[test]
+    fn record_should_check_moves() {
+        let (initial, _) = sample_journal(&CrateMover9000);
+        let series = [
+            "move 1 from 2 to 1",
+            "move 5 from 1 to 3",
+            "move 1 from 4 to 1",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(index, serie)| (index + 10, serie.parse::<Serie>().unwrap()))
+        .collect::<Vec<_>>();
+
+        let mut journal = Journal::new(initial.clone());
+        let error = journal
+            .record(&series, &CrateMover9000, Mode::Strict)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 11: move 2, move 5 from 1 to 3: stack 1 only has 3 crates"
+        );
+
+        let mut journal = Journal::new(initial.clone());
+        let warnings = journal
+            .record(&series, &CrateMover9000, Mode::Lenient)
+            .unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[1].line, 12);
+        assert_eq!(journal.entries.len(), 2);
+        let mut expected = initial;
+        expected
+            .run_procedure(&series, &CrateMover9000, Mode::Lenient)
+            .unwrap();
+        assert_eq!(journal.cargo, expected);
+    }
+
+    #[test]
+    fn should_jump_to_move() {
+        let (_, mut journal) = sample_journal(&CrateMover9000);
+        journal.jump_to(2).unwrap();
+        assert_eq!(journal.cargo.get_crate_in_top(), "CZ");
+        journal.jump_to(4).unwrap();
+        assert_eq!(journal.cargo.get_crate_in_top(), "CMZ");
+        assert!(journal.jump_to(5).is_err());
+    }
+
+    #[test]
+    fn apply_after_undo_should_drop_redo_history() {
+        let (_, mut journal) = sample_journal(&CrateMover9000);
+        journal.jump_to(1).unwrap();
+        journal
+            .apply(
+                &"move 1 from 3 to 2".parse::<Serie>().unwrap(),
+                &CrateMover9000,
+            )
+            .unwrap();
+        assert_eq!(journal.entries.len(), 2);
+        assert!(journal.redo().is_none());
+    }
+
+    #[test]
+    fn should_record_move_on_same_stack() {
+        let (initial, _) = sample_journal(&CrateMover9000);
+        let mut journal = Journal::new(initial.clone());
+        journal
+            .apply(
+                &"move 2 from 2 to 2".parse::<Serie>().unwrap(),
+                &CrateMover9000,
+            )
+            .unwrap();
+        assert_ne!(journal.cargo, initial);
+        journal.undo();
+        assert_eq!(journal.cargo, initial);
+    }
+
+    #[test]
+    fn should_diff_two_positions() {
+        let (_, journal) = sample_journal(&CrateMover9000);
+        let diff = journal.diff(0, 1).unwrap();
+        assert_eq!(
+            diff.iter().map(|d| d.to_string()).collect::<Vec<String>>(),
+            vec!["stack 1: ZN -> ZND", "stack 2: MCD -> MC"]
+        );
+        assert!(journal.diff(3, 3).unwrap().is_empty());
+    }
+
+    #[test]
+    fn replay_should_run_commands() {
+        let (_, mut journal) = sample_journal(&CrateMover9000);
+        let mut output = vec![];
+        replay(
+            &mut journal,
+            "undo\ngoto 1\ntop\nquit\nundo\n".as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "move 4/4\nundone: move 1 from 1 to 2\nmove 3/4\nmove 1/4\nDCP\nmove 1/4\n"
+        );
+    }
+}
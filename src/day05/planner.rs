@@ -0,0 +1,214 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    time::{Duration, Instant},
+};
+
+use itertools::Itertools;
+
+use super::{Cargo, Crane, Crate, Serie};
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Budget {
+    pub(super) max_nodes: usize,
+    pub(super) max_duration: Duration,
+}
+
+#[derive(Debug, PartialEq)]
+pub(super) enum Plan {
+    Found(Vec<Serie>),
+    Unreachable,
+    BudgetExceeded { explored: usize },
+}
+
+// Stacks ordered by key, used to recognise a cargo state already visited
+fn layout(cargo: &Cargo) -> Vec<Vec<Crate>> {
+    cargo
+        .stacks
+        .keys()
+        .sorted()
+        .map(|key| cargo.stacks[key].crates.clone())
+        .collect()
+}
+
+fn same_crates(start: &Cargo, goal: &Cargo) -> bool {
+    let crates = |cargo: &Cargo| {
+        cargo
+            .stacks
+            .values()
+            .flat_map(|stack| stack.crates.iter().map(|c| c.name))
+            .sorted()
+            .collect::<Vec<char>>()
+    };
+
+    start.stacks.keys().sorted().eq(goal.stacks.keys().sorted()) && crates(start) == crates(goal)
+}
+
+fn candidate_series(cargo: &Cargo) -> Vec<Serie> {
+    let keys = cargo.stacks.keys().sorted().collect::<Vec<&usize>>();
+    keys.iter()
+        .cartesian_product(keys.iter())
+        .filter(|(origin, destination)| origin != destination)
+        .flat_map(|(origin, destination)| {
+            (1..=cargo.stacks[*origin].crates.len()).map(|quantity| Serie {
+                quantity: quantity as u32,
+                origin: **origin,
+                destination: **destination,
+            })
+        })
+        .collect()
+}
+
+// Breadth first search, so the first plan found is one of the shortest
+pub(super) fn plan(start: &Cargo, goal: &Cargo, crane: &dyn Crane, budget: Budget) -> Plan {
+    if !same_crates(start, goal) {
+        return Plan::Unreachable;
+    }
+
+    let goal = layout(goal);
+    let started_at = Instant::now();
+    let mut visited = HashSet::from([layout(start)]);
+    let mut parents: Vec<Option<(usize, Serie)>> = vec![None];
+    let mut queue = VecDeque::from([(0, start.clone())]);
+
+    while let Some((node, cargo)) = queue.pop_front() {
+        if layout(&cargo) == goal {
+            let mut series = vec![];
+            let mut current = node;
+            while let Some((parent, serie)) = &parents[current] {
+                series.push(serie.clone());
+                current = *parent;
+            }
+            series.reverse();
+
+            return Plan::Found(series);
+        }
+
+        if started_at.elapsed() > budget.max_duration {
+            return Plan::BudgetExceeded {
+                explored: parents.len(),
+            };
+        }
+
+        for serie in candidate_series(&cargo) {
+            let mut next = cargo.clone();
+            if next.apply_serie(&serie, crane).is_err() || !visited.insert(layout(&next)) {
+                continue;
+            }
+            // Every state found counts, the start included
+            if parents.len() == budget.max_nodes {
+                return Plan::BudgetExceeded {
+                    explored: parents.len(),
+                };
+            }
+
+            parents.push(Some((node, serie)));
+            queue.push_back((parents.len() - 1, next));
+        }
+    }
+
+    Plan::Unreachable
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::{load_cargo, CrateMover9000, CrateMover9001};
+    use super::*;
+
+    fn cargo(lines: &[&str]) -> Cargo {
+        load_cargo(&lines.iter().map(|l| l.to_string()).collect::<Vec<String>>()).unwrap()
+    }
+
+    fn budget() -> Budget {
+        Budget {
+            max_nodes: 100_000,
+            max_duration: Duration::from_secs(10),
+        }
+    }
+
+    fn apply(start: &Cargo, series: &[Serie], crane: &dyn Crane) -> Cargo {
+        let mut cargo = start.clone();
+        for serie in series {
+            cargo.apply_serie(serie, crane).unwrap();
+        }
+        cargo
+    }
+
+    #[test]
+    fn should_find_shortest_plan() {
+        let start = cargo(&["    [D]", "[N] [C]", "[Z] [M] [P]", " 1   2   3"]);
+        let goal = cargo(&[
+            "        [Z]",
+            "        [N]",
+            "        [D]",
+            "[C] [M] [P]",
+            " 1   2   3",
+        ]);
+
+        let plan = plan(&start, &goal, &CrateMover9000, budget());
+        match plan {
+            Plan::Found(series) => {
+                assert_eq!(series.len(), 3);
+                assert_eq!(apply(&start, &series, &CrateMover9000), goal);
+            }
+            _ => panic!("no plan found: {:?}", plan),
+        }
+    }
+
+    #[test]
+    fn plan_should_depend_on_crane() {
+        let start = cargo(&["[A]", "[B]", " 1   2"]);
+        let goal = cargo(&["    [A]", "    [B]", " 1   2"]);
+
+        assert_eq!(
+            plan(&start, &goal, &CrateMover9001, budget()),
+            Plan::Found(vec!["move 2 from 1 to 2".parse().unwrap()])
+        );
+        assert_eq!(
+            plan(&start, &goal, &CrateMover9000, budget()),
+            Plan::Unreachable
+        );
+    }
+
+    #[test]
+    fn should_return_empty_plan_when_already_at_goal() {
+        let start = cargo(&["[A] [B]", " 1   2"]);
+        assert_eq!(
+            plan(&start, &start, &CrateMover9000, budget()),
+            Plan::Found(vec![])
+        );
+    }
+
+    #[test]
+    fn should_be_unreachable_with_other_crates() {
+        let start = cargo(&["[A] [B]", " 1   2"]);
+        let goal = cargo(&["[A] [C]", " 1   2"]);
+        assert_eq!(
+            plan(&start, &goal, &CrateMover9000, budget()),
+            Plan::Unreachable
+        );
+    }
+
+    #[test]
+    fn should_stop_when_budget_is_exceeded() {
+        let start = cargo(&["    [D]", "[N] [C]", "[Z] [M] [P]", " 1   2   3"]);
+        let goal = cargo(&["[D]", "[C]", "[M]", "[P]", "[N]", "[Z]", " 1   2   3"]);
+        let budget = Budget {
+            max_nodes: 10,
+            max_duration: Duration::from_secs(10),
+        };
+        assert_eq!(
+            plan(&start, &goal, &CrateMover9000, budget),
+            Plan::BudgetExceeded { explored: 10 }
+        );
+        // The first expansion alone finds more than 2 states
+        let budget = Budget {
+            max_nodes: 2,
+            ..budget
+        };
+        assert_eq!(
+            plan(&start, &goal, &CrateMover9000, budget),
+            Plan::BudgetExceeded { explored: 2 }
+        );
+    }
+}
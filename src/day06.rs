@@ -1,69 +1,213 @@
-use anyhow::anyhow;
-use std::{
-    fs::File,
-    io::{BufRead, BufReader},
-};
-
-fn load_data_stream(path: &str) -> anyhow::Result<String> {
-    Ok(BufReader::new(File::open(path)?)
-        .lines()
-        .next()
-        .ok_or_else(|| anyhow!("No data found"))??)
-}
-
-fn subroutine(data_stream: &str, marker_size: usize) -> usize {
-    let mut marker: Vec<char> = vec![];
-    let mut marker_index = 0;
-    for (index, letter) in data_stream.chars().enumerate() {
-        if let Some(position) = marker.iter().position(|&c| c == letter) {
-            for _ in 0..=position {
-                marker.remove(0);
-            }
-        }
-
-        marker.push(letter);
-        marker_index = index;
-
-        if marker.len() == marker_size {
-            break;
-        }
-    }
-
-    marker_index + 1
-}
-
-pub fn day06() -> anyhow::Result<()> {
-    let data_stream = load_data_stream("data/day06.txt")?;
-
-    println!(
-        "Day 06 start-of-packet marker: {}",
-        subroutine(&data_stream, 4)
-    );
-    println!("Day 06 messages: {}", subroutine(&data_stream, 14));
-
-    Ok(())
-}
-
-#[cfg(test)]
-mod tests {
-
-    use super::*;
-
-    #[test]
-    fn should_return_subroutine_of_marker() {
-        assert_eq!(subroutine("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 4), 7);
-        assert_eq!(subroutine("bvwbjplbgvbhsrlpgdmjqwftvncz", 4), 5);
-        assert_eq!(subroutine("nppdvjthqldpwncqszvftbrmjlhg", 4), 6);
-        assert_eq!(subroutine("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 4), 10);
-        assert_eq!(subroutine("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 4), 11);
-    }
-
-    #[test]
-    fn should_return_subroutine_of_message() {
-        assert_eq!(subroutine("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 14), 19);
-        assert_eq!(subroutine("bvwbjplbgvbhsrlpgdmjqwftvncz", 14), 23);
-        assert_eq!(subroutine("nppdvjthqldpwncqszvftbrmjlhg", 14), 23);
-        assert_eq!(subroutine("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 14), 29);
-        assert_eq!(subroutine("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 14), 26);
-    }
-}
+use anyhow::anyhow;
+use std::{fs::File, io::BufReader};
+
+use crate::cli::positionals;
+
+mod decoder;
+mod scanner;
+
+use decoder::decode;
+use scanner::{MarkerKind, MarkerScanner};
+
+// Finds markers one byte at a time: the window start jumps past the last occurrence of each
+// byte, so the stream is read only once whatever the marker size. Once a marker is found the
+// window restarts after it, so the following markers never overlap it.
+#[derive(Debug, Clone)]
+struct MarkerDetector {
+    marker_size: usize,
+    last_seen: [usize; 256],
+    window_start: usize,
+    position: usize,
+}
+
+impl MarkerDetector {
+    fn new(marker_size: usize) -> Self {
+        Self {
+            marker_size,
+            last_seen: [0; 256],
+            window_start: 0,
+            position: 0,
+        }
+    }
+
+    fn push(&mut self, byte: u8) -> Option<usize> {
+        self.position += 1;
+        self.window_start = self.window_start.max(self.last_seen[byte as usize]);
+        self.last_seen[byte as usize] = self.position;
+
+        if self.position - self.window_start == self.marker_size {
+            self.window_start = self.position;
+            return Some(self.position);
+        }
+
+        None
+    }
+}
+
+// Position after the first `marker_size` distinct bytes, the in-memory reference the scanner
+// is checked against
+#[cfg(test)]
+fn subroutine(data_stream: &str, marker_size: usize) -> Option<usize> {
+    if marker_size == 0 {
+        return Some(0);
+    }
+
+    let mut detector = MarkerDetector::new(marker_size);
+    data_stream.bytes().find_map(|byte| detector.push(byte))
+}
+
+// Reads the capture until both first markers are found
+fn solve(path: &str) -> anyhow::Result<()> {
+    let mut packet = None;
+    let mut message = None;
+    for marker in MarkerScanner::new(BufReader::new(File::open(path)?)) {
+        let marker = marker?;
+        match marker.kind {
+            MarkerKind::StartOfPacket => packet = packet.or(Some(marker.position)),
+            MarkerKind::StartOfMessage => message = message.or(Some(marker.position)),
+        }
+        if packet.is_some() && message.is_some() {
+            break;
+        }
+    }
+
+    println!(
+        "Day 06 start-of-packet marker: {}",
+        packet.ok_or_else(|| anyhow!("No start-of-packet marker found"))?
+    );
+    println!(
+        "Day 06 messages: {}",
+        message.ok_or_else(|| anyhow!("No start-of-message marker found"))?
+    );
+
+    Ok(())
+}
+
+pub fn day06() -> anyhow::Result<()> {
+    solve("data/day06.txt")
+}
+
+fn scan(path: &str) -> anyhow::Result<()> {
+    for marker in MarkerScanner::new(BufReader::new(File::open(path)?)) {
+        let marker = marker?;
+        println!("Day 06 {}: {}", marker.kind, marker.position);
+    }
+
+    Ok(())
+}
+
+fn print_frames(path: &str) -> anyhow::Result<()> {
+    for frame in decode(BufReader::new(File::open(path)?)) {
+        println!("Day 06 {}", frame?);
+    }
+
+    Ok(())
+}
+
+// Usage: day06 [path]
+//        day06 scan [path]
+//        day06 decode [path]
+pub fn cli(args: &[String]) -> anyhow::Result<()> {
+    let positionals = positionals(args);
+    let path = positionals.get(1).copied().unwrap_or("data/day06.txt");
+    match positionals.first().copied() {
+        Some("scan") => scan(path),
+        Some("decode") => print_frames(path),
+        path => solve(path.unwrap_or("data/day06.txt")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::time::Instant;
+
+    // Previous implementation, kept to compare against in the benchmark
+    fn naive_subroutine(data_stream: &str, marker_size: usize) -> usize {
+        let mut marker: Vec<char> = vec![];
+        let mut marker_index = 0;
+        for (index, letter) in data_stream.chars().enumerate() {
+            if let Some(position) = marker.iter().position(|&c| c == letter) {
+                for _ in 0..=position {
+                    marker.remove(0);
+                }
+            }
+
+            marker.push(letter);
+            marker_index = index;
+
+            if marker.len() == marker_size {
+                break;
+            }
+        }
+
+        marker_index + 1
+    }
+
+    #[test]
+    fn should_return_subroutine_of_marker() {
+        assert_eq!(subroutine("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 4), Some(7));
+        assert_eq!(subroutine("bvwbjplbgvbhsrlpgdmjqwftvncz", 4), Some(5));
+        assert_eq!(subroutine("nppdvjthqldpwncqszvftbrmjlhg", 4), Some(6));
+        assert_eq!(subroutine("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 4), Some(10));
+        assert_eq!(subroutine("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 4), Some(11));
+    }
+
+    #[test]
+    fn should_return_subroutine_of_message() {
+        assert_eq!(subroutine("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 14), Some(19));
+        assert_eq!(subroutine("bvwbjplbgvbhsrlpgdmjqwftvncz", 14), Some(23));
+        assert_eq!(subroutine("nppdvjthqldpwncqszvftbrmjlhg", 14), Some(23));
+        assert_eq!(
+            subroutine("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 14),
+            Some(29)
+        );
+        assert_eq!(subroutine("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 14), Some(26));
+    }
+
+    #[test]
+    fn should_return_none_without_marker() {
+        assert_eq!(subroutine("", 4), None);
+        assert_eq!(subroutine("abc", 4), None);
+        assert_eq!(subroutine("abcabcabcabc", 4), None);
+        assert_eq!(subroutine("abcd", 4), Some(4));
+        assert_eq!(subroutine("aaaa", 1), Some(1));
+    }
+
+    #[test]
+    fn should_match_naive_implementation() {
+        let stream = (0..5000u32)
+            .map(|i| (b'a' + ((i * 7919) % 23 % 17) as u8) as char)
+            .collect::<String>();
+        for marker_size in 1..=17 {
+            if let Some(position) = subroutine(&stream, marker_size) {
+                assert_eq!(position, naive_subroutine(&stream, marker_size));
+            }
+        }
+    }
+
+    // cargo test --release -- --ignored --nocapture day06
+    #[test]
+    #[ignore]
+    fn bench_subroutine_on_large_stream() {
+        let mut stream = "abcdefghijklm".repeat(20_000_000);
+        stream.push_str("nopqrstuvwxyz");
+
+        for marker_size in [4, 14, 26] {
+            let start = Instant::now();
+            let linear = subroutine(&stream, marker_size);
+            let linear_time = start.elapsed();
+
+            let start = Instant::now();
+            let naive = naive_subroutine(&stream, marker_size);
+            let naive_time = start.elapsed();
+
+            assert_eq!(linear, Some(naive));
+            println!(
+                "marker size {}: linear {:?}, naive {:?}",
+                marker_size, linear_time, naive_time
+            );
+        }
+    }
+}
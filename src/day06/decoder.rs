@@ -0,0 +1,266 @@
+use anyhow::anyhow;
+use std::{fmt, io::Read, mem};
+
+use super::scanner::{MarkerKind, MarkerScanner};
+
+// `offset` is where the payload starts, right after the start-of-message marker
+#[derive(Debug, PartialEq)]
+pub(super) struct Message {
+    offset: usize,
+    payload: String,
+}
+
+// A frame starts with a start-of-packet marker and runs until the next one. Markers of a
+// kind never overlap, as in the scanner. A message belongs to the frame its payload starts
+// in, and the payload runs until the end of that frame.
+#[derive(Debug, PartialEq)]
+pub(super) struct Frame {
+    offset: usize,
+    length: usize,
+    messages: Vec<Message>,
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "frame at {} ({} bytes)", self.offset, self.length)?;
+        if self.messages.is_empty() {
+            return write!(f, ", no message");
+        }
+        for message in self.messages.iter() {
+            write!(f, ", message at {}: {:?}", message.offset, message.payload)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Offsets are 0-based positions in the signal, line breaks of the capture are skipped as in
+// the scanner. Bytes before the first start-of-packet marker are not part of any frame.
+pub(super) fn decode<R: Read>(reader: R) -> Decoder<R> {
+    Decoder {
+        scanner: MarkerScanner::new(reader).keeping_signal(),
+        signal: vec![],
+        signal_start: 0,
+        frame: None,
+        messages: vec![],
+        done: false,
+    }
+}
+
+// Yields each frame once the next one starts, so only the current frame is kept in memory
+pub(super) struct Decoder<R: Read> {
+    scanner: MarkerScanner<R>,
+    // Signal from `signal_start` up to the last marker read
+    signal: Vec<u8>,
+    signal_start: usize,
+    frame: Option<usize>,
+    // Payload starts not assigned to a frame yet
+    messages: Vec<usize>,
+    done: bool,
+}
+
+impl<R: Read> Decoder<R> {
+    fn read_signal(&mut self) -> anyhow::Result<()> {
+        let signal = self.scanner.take_signal();
+        if !signal.is_ascii() {
+            return Err(anyhow!(
+                "Invalid data stream: only ASCII signals can be decoded"
+            ));
+        }
+        self.signal.extend(signal);
+
+        Ok(())
+    }
+
+    fn payload(&self, start: usize, end: usize) -> String {
+        String::from_utf8_lossy(&self.signal[start - self.signal_start..end - self.signal_start])
+            .into_owned()
+    }
+
+    // A message ending the stream is kept with an empty payload
+    fn close_frame(&mut self, end: usize, last: bool) -> Option<Frame> {
+        let (messages, next): (Vec<usize>, Vec<usize>) = mem::take(&mut self.messages)
+            .into_iter()
+            .partition(|&start| start < end || last);
+        self.messages = next;
+
+        let frame = self.frame.map(|offset| Frame {
+            offset,
+            length: end - offset,
+            messages: messages
+                .into_iter()
+                .map(|start| Message {
+                    offset: start,
+                    payload: self.payload(start, end),
+                })
+                .collect(),
+        });
+        self.signal.drain(..end - self.signal_start);
+        self.signal_start = end;
+
+        frame
+    }
+}
+
+impl<R: Read> Iterator for Decoder<R> {
+    type Item = anyhow::Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let marker = match self.scanner.next().transpose() {
+                Ok(marker) => marker,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e.into()));
+                }
+            };
+            if let Err(e) = self.read_signal() {
+                self.done = true;
+                return Some(Err(e));
+            }
+
+            match marker {
+                Some(marker) if marker.kind == MarkerKind::StartOfMessage => {
+                    self.messages.push(marker.position)
+                }
+                Some(marker) => {
+                    let start = marker.position - marker.kind.size();
+                    let frame = self.close_frame(start, false);
+                    self.frame = Some(start);
+                    if frame.is_some() {
+                        return frame.map(Ok);
+                    }
+                }
+                None => {
+                    self.done = true;
+                    let end = self.signal_start + self.signal.len();
+                    return self.close_frame(end, true).map(Ok);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn decode_all(data_stream: &str) -> anyhow::Result<Vec<Frame>> {
+        decode(data_stream.as_bytes()).collect()
+    }
+
+    // Frame offsets, and each message with its payload
+    fn summary(data_stream: &str) -> (Vec<usize>, Vec<(usize, String)>) {
+        let frames = decode_all(data_stream).unwrap();
+        let offsets = frames.iter().map(|frame| frame.offset).collect();
+        let messages = frames
+            .iter()
+            .flat_map(|frame| frame.messages.iter())
+            .map(|message| (message.offset, message.payload.clone()))
+            .collect();
+        (offsets, messages)
+    }
+
+    #[test]
+    fn should_decode_sample_streams() {
+        assert_eq!(
+            summary("mjqjpqmgbljsphdztnvjfqwrcgsmlb"),
+            (vec![3, 7, 11, 15, 19, 23], vec![(19, "jfqw".to_string())])
+        );
+        assert_eq!(
+            summary("bvwbjplbgvbhsrlpgdmjqwftvncz"),
+            (vec![1, 5, 9, 13, 17, 21], vec![(23, "tvncz".to_string())])
+        );
+        assert_eq!(
+            summary("nppdvjthqldpwncqszvftbrmjlhg"),
+            (vec![2, 6, 10, 14, 18, 22], vec![(23, "mjlhg".to_string())])
+        );
+        assert_eq!(
+            summary("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"),
+            (vec![6, 10, 14, 18, 22, 26], vec![(29, "prsg".to_string())])
+        );
+        assert_eq!(
+            summary("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"),
+            (vec![7, 11, 15, 19, 23, 27], vec![(26, "s".to_string())])
+        );
+    }
+
+    #[test]
+    fn should_split_frames_without_message() {
+        let frames = decode_all("abcdaaaaefghaaaaijkl").unwrap();
+        assert_eq!(
+            frames
+                .iter()
+                .map(|frame| (frame.offset, frame.length))
+                .collect::<Vec<_>>(),
+            vec![(0, 7), (7, 8), (15, 5)]
+        );
+        assert!(frames.iter().all(|frame| frame.messages.is_empty()));
+    }
+
+    #[test]
+    fn should_keep_message_ending_the_stream() {
+        assert_eq!(
+            decode_all("abcdefghijklmn").unwrap(),
+            vec![
+                Frame {
+                    offset: 0,
+                    length: 4,
+                    messages: vec![],
+                },
+                Frame {
+                    offset: 4,
+                    length: 4,
+                    messages: vec![],
+                },
+                Frame {
+                    offset: 8,
+                    length: 6,
+                    messages: vec![Message {
+                        offset: 14,
+                        payload: String::new(),
+                    }],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_skip_line_breaks_inside_frames() {
+        assert_eq!(
+            summary("mjqjpqmgbljs\r\nphdztnvjf\nqwrcgsmlb\n"),
+            summary("mjqjpqmgbljsphdztnvjfqwrcgsmlb")
+        );
+        assert_eq!(
+            decode_all("abcdefghijklm\r\nnopq").unwrap()[3],
+            Frame {
+                offset: 12,
+                length: 5,
+                messages: vec![Message {
+                    offset: 14,
+                    payload: "opq".to_string(),
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn should_decode_stream_without_marker() {
+        assert!(decode_all("").unwrap().is_empty());
+        assert!(decode_all("abcabcabc").unwrap().is_empty());
+        assert!(decode_all("é").is_err());
+    }
+
+    #[test]
+    fn should_display_frame() {
+        let frames = decode_all("mjqjpqmgbljsphdztnvjfqwrcgsmlb").unwrap();
+        assert_eq!(frames[0].to_string(), "frame at 3 (4 bytes), no message");
+        assert_eq!(
+            frames[4].to_string(),
+            "frame at 19 (4 bytes), message at 19: \"jfqw\""
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    io::{ErrorKind, Read},
+    mem,
+};
+
+use super::MarkerDetector;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum MarkerKind {
+    StartOfPacket,
+    StartOfMessage,
+}
+
+impl MarkerKind {
+    pub(super) fn size(&self) -> usize {
+        match self {
+            MarkerKind::StartOfPacket => 4,
+            MarkerKind::StartOfMessage => 14,
+        }
+    }
+}
+
+impl fmt::Display for MarkerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkerKind::StartOfPacket => write!(f, "start-of-packet marker"),
+            MarkerKind::StartOfMessage => write!(f, "start-of-message marker"),
+        }
+    }
+}
+
+// `position` counts the characters of the signal up to the end of the marker, line breaks
+// of the capture are not part of the signal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct Marker {
+    pub(super) kind: MarkerKind,
+    pub(super) position: usize,
+}
+
+const CHUNK_SIZE: usize = 64 * 1024;
+const KINDS: [MarkerKind; 2] = [MarkerKind::StartOfPacket, MarkerKind::StartOfMessage];
+
+// Reads the stream by chunks and yields every marker as soon as its last character is read,
+// the detectors keep their window from one chunk to the next
+pub(super) struct MarkerScanner<R: Read> {
+    reader: R,
+    chunk: Vec<u8>,
+    filled: usize,
+    offset: usize,
+    detectors: Vec<MarkerDetector>,
+    pending: VecDeque<Marker>,
+    signal: Option<Vec<u8>>,
+}
+
+impl<R: Read> MarkerScanner<R> {
+    pub(super) fn new(reader: R) -> Self {
+        Self::with_chunk_size(reader, CHUNK_SIZE)
+    }
+
+    pub(super) fn with_chunk_size(reader: R, chunk_size: usize) -> Self {
+        Self {
+            reader,
+            chunk: vec![0; chunk_size.max(1)],
+            filled: 0,
+            offset: 0,
+            detectors: KINDS
+                .iter()
+                .map(|kind| MarkerDetector::new(kind.size()))
+                .collect(),
+            pending: VecDeque::new(),
+            signal: None,
+        }
+    }
+
+    // Keeps the signal read so far, without line breaks, until `take_signal` is called
+    pub(super) fn keeping_signal(mut self) -> Self {
+        self.signal = Some(vec![]);
+        self
+    }
+
+    // Signal read since the last call, up to the end of the last marker yielded
+    pub(super) fn take_signal(&mut self) -> Vec<u8> {
+        self.signal.as_mut().map(mem::take).unwrap_or_default()
+    }
+
+    fn scan_chunk(&mut self) {
+        while self.pending.is_empty() && self.offset < self.filled {
+            let byte = self.chunk[self.offset];
+            self.offset += 1;
+            if byte == b'\n' || byte == b'\r' {
+                continue;
+            }
+            if let Some(signal) = self.signal.as_mut() {
+                signal.push(byte);
+            }
+
+            for (kind, detector) in KINDS.iter().zip(self.detectors.iter_mut()) {
+                if let Some(position) = detector.push(byte) {
+                    self.pending.push_back(Marker {
+                        kind: *kind,
+                        position,
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for MarkerScanner<R> {
+    type Item = std::io::Result<Marker>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.scan_chunk();
+            if let Some(marker) = self.pending.pop_front() {
+                return Some(Ok(marker));
+            }
+
+            match self.reader.read(&mut self.chunk) {
+                Ok(0) => return None,
+                Ok(filled) => {
+                    self.filled = filled;
+                    self.offset = 0;
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::subroutine;
+    use super::*;
+
+    fn scan(data: &str, chunk_size: usize) -> Vec<Marker> {
+        MarkerScanner::with_chunk_size(data.as_bytes(), chunk_size)
+            .collect::<std::io::Result<Vec<Marker>>>()
+            .unwrap()
+    }
+
+    fn positions(markers: &[Marker], kind: MarkerKind) -> Vec<usize> {
+        markers
+            .iter()
+            .filter(|marker| marker.kind == kind)
+            .map(|marker| marker.position)
+            .collect()
+    }
+
+    #[test]
+    fn first_markers_should_match_subroutine() {
+        for data in [
+            "mjqjpqmgbljsphdztnvjfqwrcgsmlb",
+            "bvwbjplbgvbhsrlpgdmjqwftvncz",
+            "nppdvjthqldpwncqszvftbrmjlhg",
+            "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg",
+            "zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw",
+        ] {
+            let markers = scan(data, 3);
+            for kind in KINDS {
+                assert_eq!(
+                    positions(&markers, kind).first().copied(),
+                    subroutine(data, kind.size())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn should_report_every_marker() {
+        let markers = scan("aabcdxxefghyyijklmnopqrstuvw", 5);
+        assert_eq!(
+            positions(&markers, MarkerKind::StartOfPacket),
+            vec![5, 10, 16, 20, 24, 28]
+        );
+        assert_eq!(positions(&markers, MarkerKind::StartOfMessage), vec![26]);
+    }
+
+    #[test]
+    fn chunk_size_should_not_change_markers() {
+        let data = "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg".repeat(50);
+        let expected = scan(&data, CHUNK_SIZE);
+        for chunk_size in [1, 2, 3, 7, 64] {
+            assert_eq!(scan(&data, chunk_size), expected);
+        }
+    }
+
+    #[test]
+    fn should_skip_line_breaks() {
+        assert_eq!(
+            scan("mjqj\r\npqmgb\nljsphdztnvjfqwrcgsmlb\n", 2),
+            scan("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 2)
+        );
+    }
+
+    #[test]
+    fn should_keep_signal_up_to_each_marker() {
+        let mut scanner = MarkerScanner::with_chunk_size("mj\nqjpqm\r\ngb".as_bytes(), 3);
+        assert!(scanner.take_signal().is_empty());
+
+        let mut scanner = scanner.keeping_signal();
+        assert_eq!(scanner.next().unwrap().unwrap().position, 7);
+        assert_eq!(scanner.take_signal(), b"mjqjpqm");
+        assert!(scanner.next().is_none());
+        assert_eq!(scanner.take_signal(), b"gb");
+    }
+}
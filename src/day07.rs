@@ -1,279 +1,524 @@
-use std::{
-    collections::HashMap,
-    io::{BufRead, BufReader},
-    str::FromStr,
-};
-
-#[derive(Debug, PartialEq)]
-struct File {
-    name: String,
-    size: u64,
-}
-
-impl File {
-    fn new(name: String, size: u64) -> Self {
-        Self { name, size }
-    }
-}
-
-impl FromStr for File {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.split_whitespace();
-        let size = parts.next().unwrap().parse::<u64>()?;
-        let name = parts.next().unwrap().to_string();
-        Ok(Self::new(name, size))
-    }
-}
-
-#[derive(Debug)]
-struct Directory {
-    name: String,
-    path: String,
-    files: Vec<File>,
-    directories: Vec<String>,
-}
-
-impl Directory {
-    fn new(name: String, path: String) -> Self {
-        Self {
-            name,
-            path,
-            files: Vec::new(),
-            directories: Vec::new(),
-        }
-    }
-
-    fn push(&mut self, file: File) {
-        self.files.push(file);
-    }
-
-    fn push_dir(&mut self, dir: String) {
-        self.directories.push(dir);
-    }
-
-    fn du(&self) -> u64 {
-        self.files.iter().map(|f| f.size).sum()
-    }
-}
-
-#[derive(Debug)]
-struct FileSystem {
-    working_directory: String,
-    directories: HashMap<String, Directory>,
-}
-
-impl FileSystem {
-    fn new() -> Self {
-        Self {
-            working_directory: String::new(),
-            directories: HashMap::new(),
-        }
-    }
-
-    fn move_forward(&mut self, name: String) {
-        self.working_directory = format!("{}/{}", self.working_directory, name);
-    }
-
-    fn move_back(&mut self) {
-        let mut path = self.working_directory.split('/').collect::<Vec<&str>>();
-        path.pop();
-        self.working_directory = path.join("/");
-    }
-
-    fn move_to_root(&mut self) {
-        self.working_directory = String::new();
-    }
-
-    fn pwd(&self) -> String {
-        if self.working_directory.is_empty() {
-            return "/".to_string();
-        }
-        self.working_directory.clone()
-    }
-
-    fn mkdir(&mut self) -> &mut Directory {
-        let path = self.pwd();
-        let name = self.pwd().split('/').last().unwrap().to_string();
-        self.directories
-            .entry(self.pwd())
-            .or_insert_with(|| Directory::new(name, path))
-    }
-
-    fn create_file_to_current_directory(&mut self, file: File) {
-        let dir = self.mkdir();
-        dir.push(file);
-    }
-
-    fn create_directory_in_current_directory(&mut self, name: String) {
-        let dir = self.mkdir();
-        dir.push_dir(name);
-    }
-}
-
-fn load_file_system(path: &str) -> anyhow::Result<FileSystem> {
-    let mut fs = FileSystem::new();
-    let file = std::fs::File::open(path)?;
-    let reader = BufReader::new(file);
-    let mut lines = reader.lines();
-    loop {
-        let line = lines.next();
-        if line.is_none() {
-            break;
-        }
-        let line = line.unwrap()?;
-        // if line.is_empty() {
-        //     break;
-        // }
-        let mut parts = line.split_whitespace();
-        match (parts.next(), parts.next(), parts.next(), parts.next()) {
-            (Some("$"), Some("cd"), Some("/"), None) => fs.move_to_root(),
-            (Some("$"), Some("cd"), Some(".."), None) => fs.move_back(),
-            (Some("$"), Some("cd"), Some(name), None) => fs.move_forward(name.to_string()),
-            (Some("$"), Some("ls"), None, None) => {}
-            (Some("dir"), Some(name), None, None) => {
-                fs.create_directory_in_current_directory(name.to_string())
-            }
-            (Some(size), Some(name), None, None) => fs.create_file_to_current_directory(File::new(
-                name.to_string(),
-                size.parse::<u64>()?,
-            )),
-            _ => {}
-        }
-    }
-
-    Ok(fs)
-}
-
-fn compute_directory_size(fs: &FileSystem, dir: &Directory) -> u64 {
-    let mut size = dir.du();
-
-    if dir.directories.is_empty() {
-        return size;
-    }
-
-    for directory_name in dir.directories.iter() {
-        let mut sub_dir_path = format!("{}/{}", dir.path, directory_name).to_owned();
-        if dir.name.is_empty() {
-            sub_dir_path = format!("/{}", directory_name).to_owned();
-        }
-
-        if let Some(dir) = fs.directories.get(&sub_dir_path) {
-            size += compute_directory_size(fs, dir)
-        }
-    }
-
-    size
-}
-
-pub fn day07() -> anyhow::Result<()> {
-    let fs = load_file_system("data/day07.txt")?;
-
-    println!(
-        "Day 07 part 1: {:?}",
-        fs.directories.iter().fold(0, |acc: u64, (_, dir)| {
-            let size = compute_directory_size(&fs, dir);
-            if size <= 100000 {
-                return acc + size;
-            }
-
-            acc
-        })
-    );
-
-    let disk_space = 70000000;
-    let update_size = 30000000;
-    let total_size = fs.directories.iter().map(|(_, dir)| dir.du()).sum::<u64>();
-    let used_space = disk_space - total_size;
-    let needed_space = update_size - used_space;
-
-    println!(
-        "total_size: {}, used_space: {}, neededspace {}",
-        total_size, used_space, needed_space
-    );
-    println!(
-        "Day 07 part 2 {:?}",
-        fs.directories
-            .iter()
-            .fold(disk_space, |acc: u64, (_, dir)| {
-                let size = compute_directory_size(&fs, dir);
-                if size >= needed_space && size < acc {
-                    return size;
-                }
-
-                acc
-            })
-    );
-
-    Ok(())
-}
-
-#[cfg(test)]
-mod tests {
-
-    use super::*;
-
-    #[test]
-    fn working_directory_should_be_expected() {
-        let mut fs = FileSystem::new();
-        fs.move_forward("a".to_string());
-        assert_eq!(fs.pwd(), "/a");
-    }
-
-    #[test]
-    fn working_directory_should_be_root() {
-        let fs = FileSystem::new();
-        assert_eq!(fs.pwd(), "/");
-    }
-
-    #[test]
-    fn working_directory_should_be_root_with_move_back_from_root() {
-        let mut fs = FileSystem::new();
-        fs.move_back();
-        assert_eq!(fs.pwd(), "/");
-    }
-
-    #[test]
-    fn working_directory_should_be_root_with_move_to_root() {
-        let mut fs = FileSystem::new();
-        fs.move_forward("a".to_string());
-        fs.move_to_root();
-        assert_eq!(fs.pwd(), "/");
-    }
-
-    #[test]
-    fn mkdir_should_create_directory() {
-        let mut fs = FileSystem::new();
-        let dir = fs.mkdir();
-        assert_eq!(dir.name, "");
-        assert_eq!(dir.path, "/");
-        assert_eq!(fs.directories.len(), 1);
-    }
-
-    #[test]
-    fn mkdir_should_not_create_directory_if_already_exist() {
-        let mut fs = FileSystem::new();
-        fs.mkdir();
-        let dir = fs.mkdir();
-        assert_eq!(dir.name, "");
-        assert_eq!(dir.path, "/");
-        assert_eq!(fs.directories.len(), 1);
-    }
-
-    #[test]
-    fn should_parse_file() {
-        assert_eq!(
-            "14848514 b.txt".parse::<File>().unwrap(),
-            File::new("b.txt".to_string(), 14848514)
-        );
-        assert_eq!(
-            "8504156 c.dat".parse::<File>().unwrap(),
-            File::new("c.dat".to_string(), 8504156)
-        );
-        assert_eq!(
-            "29116 f".parse::<File>().unwrap(),
-            File::new("f".to_string(), 29116)
-        );
-    }
-}
+use anyhow::anyhow;
+use std::{
+    io::{BufRead, BufReader},
+    str::FromStr,
+};
+
+use crate::cli::{flag, option, positionals};
+use crate::validation::Mode;
+
+mod parser;
+mod query;
+mod shell;
+mod transcript;
+
+use parser::parse;
+use query::{du, find, largest_directories, listing, tree, Filter, Format, Kind};
+use transcript::{check_round_trip, from_disk, random_file_system, transcript};
+
+#[derive(Debug, PartialEq)]
+struct File {
+    name: String,
+    size: u64,
+}
+
+impl File {
+    fn new(name: String, size: u64) -> Self {
+        Self { name, size }
+    }
+}
+
+impl FromStr for File {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_whitespace().collect::<Vec<&str>>()[..] {
+            [size, name] => {
+                let size = size
+                    .parse::<u64>()
+                    .map_err(|_| anyhow!("Invalid file size in {:?}", s))?;
+                Ok(Self::new(name.to_string(), size))
+            }
+            _ => Err(anyhow!("Invalid file {:?}", s)),
+        }
+    }
+}
+
+type NodeId = usize;
+
+const ROOT: NodeId = 0;
+
+#[derive(Debug, PartialEq)]
+enum NodeKind {
+    Directory { children: Vec<NodeId> },
+    File,
+}
+
+// For a directory, `size` is the recursive size of everything below it, kept up to date as
+// files are added
+#[derive(Debug, PartialEq)]
+struct Node {
+    name: String,
+    parent: Option<NodeId>,
+    size: u64,
+    kind: NodeKind,
+}
+
+impl Node {
+    fn is_directory(&self) -> bool {
+        matches!(self.kind, NodeKind::Directory { .. })
+    }
+
+    fn children(&self) -> &[NodeId] {
+        match &self.kind {
+            NodeKind::Directory { children } => children,
+            NodeKind::File => &[],
+        }
+    }
+}
+
+// Nodes live in a single arena and refer to each other by index, the root is the first node
+#[derive(Debug)]
+struct FileSystem {
+    working_directory: NodeId,
+    nodes: Vec<Node>,
+}
+
+impl FileSystem {
+    fn new() -> Self {
+        Self {
+            working_directory: ROOT,
+            nodes: vec![Node {
+                name: String::new(),
+                parent: None,
+                size: 0,
+                kind: NodeKind::Directory { children: vec![] },
+            }],
+        }
+    }
+
+    fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    fn child(&self, parent: NodeId, name: &str) -> Option<NodeId> {
+        self.node(parent)
+            .children()
+            .iter()
+            .copied()
+            .find(|&id| self.node(id).name == name)
+    }
+
+    fn add_node(&mut self, parent: NodeId, name: String, size: u64, kind: NodeKind) -> NodeId {
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            name,
+            parent: Some(parent),
+            size: 0,
+            kind,
+        });
+        if let NodeKind::Directory { children } = &mut self.nodes[parent].kind {
+            children.push(id);
+        }
+        self.grow(id, size);
+
+        id
+    }
+
+    // Adds `size` to a node and all its ancestors
+    fn grow(&mut self, id: NodeId, size: u64) {
+        let mut current = Some(id);
+        while let Some(id) = current {
+            self.nodes[id].size += size;
+            current = self.nodes[id].parent;
+        }
+    }
+
+    fn shrink(&mut self, id: NodeId, size: u64) {
+        let mut current = Some(id);
+        while let Some(id) = current {
+            self.nodes[id].size -= size;
+            current = self.nodes[id].parent;
+        }
+    }
+
+    // Unlinks a node from its parent, the nodes below it stay in the arena but can no longer
+    // be reached from the root
+    fn remove(&mut self, id: NodeId) {
+        if let Some(parent) = self.node(id).parent {
+            self.shrink(parent, self.node(id).size);
+            if let NodeKind::Directory { children } = &mut self.nodes[parent].kind {
+                children.retain(|&child| child != id);
+            }
+            self.nodes[id].parent = None;
+        }
+    }
+
+    // Every node reachable from `id`, itself included, parents before their children
+    fn descendants(&self, id: NodeId) -> Vec<NodeId> {
+        let mut ids = vec![];
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            ids.push(id);
+            stack.extend(self.node(id).children().iter().rev());
+        }
+
+        ids
+    }
+
+    fn total_used(&self) -> u64 {
+        self.node(ROOT).size
+    }
+
+    fn free(&self, disk_space: u64) -> u64 {
+        disk_space.saturating_sub(self.total_used())
+    }
+
+    // None when nothing needs to be freed or when no directory is big enough
+    fn smallest_dir_to_free(&self, needed: u64) -> Option<NodeId> {
+        if needed == 0 {
+            return None;
+        }
+
+        self.directories()
+            .filter(|&id| self.node(id).size >= needed)
+            .min_by_key(|&id| self.node(id).size)
+    }
+
+    // Space to free on a disk of `disk_space` bytes before installing the update
+    fn needed_for_update(&self, disk_space: u64, update_size: u64) -> u64 {
+        update_size.saturating_sub(self.free(disk_space))
+    }
+
+    fn dir_to_delete_for_update(&self, disk_space: u64, update_size: u64) -> Option<NodeId> {
+        self.smallest_dir_to_free(self.needed_for_update(disk_space, update_size))
+    }
+
+    fn directories(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.descendants(ROOT)
+            .into_iter()
+            .filter(|&id| self.node(id).is_directory())
+    }
+
+    fn path(&self, id: NodeId) -> String {
+        let mut names = vec![];
+        let mut current = id;
+        while let Some(parent) = self.node(current).parent {
+            names.push(self.node(current).name.as_str());
+            current = parent;
+        }
+        names.reverse();
+
+        format!("/{}", names.join("/"))
+    }
+
+    fn move_forward(&mut self, name: String) {
+        self.working_directory = self.create_directory_in_current_directory(name);
+    }
+
+    fn move_back(&mut self) {
+        if let Some(parent) = self.node(self.working_directory).parent {
+            self.working_directory = parent;
+        }
+    }
+
+    fn move_to_root(&mut self) {
+        self.working_directory = ROOT;
+    }
+
+    fn pwd(&self) -> String {
+        self.path(self.working_directory)
+    }
+
+    fn create_file_to_current_directory(&mut self, file: File) {
+        self.add_node(self.working_directory, file.name, file.size, NodeKind::File);
+    }
+
+    // Listing a directory already known returns the existing node
+    fn create_directory_in_current_directory(&mut self, name: String) -> NodeId {
+        match self.child(self.working_directory, &name) {
+            Some(id) => id,
+            None => self.add_node(
+                self.working_directory,
+                name,
+                0,
+                NodeKind::Directory { children: vec![] },
+            ),
+        }
+    }
+}
+
+fn load_file_system(path: &str, mode: Mode) -> anyhow::Result<FileSystem> {
+    let file = std::fs::File::open(path)?;
+    let (fs, warnings) = parse(BufReader::new(file), mode)?;
+    for warning in warnings {
+        eprintln!("Day 07 warning, {}", warning);
+    }
+
+    Ok(fs)
+}
+
+fn parse_file_system<R: BufRead>(reader: R) -> anyhow::Result<FileSystem> {
+    Ok(parse(reader, Mode::Strict)?.0)
+}
+
+fn sum_of_small_directories(fs: &FileSystem) -> u64 {
+    fs.directories()
+        .map(|id| fs.node(id).size)
+        .filter(|&size| size <= 100000)
+        .sum()
+}
+
+const DISK_SPACE: u64 = 70000000;
+const UPDATE_SIZE: u64 = 30000000;
+
+fn solve(path: &str, mode: Mode, disk_space: u64, update_size: u64) -> anyhow::Result<()> {
+    let fs = load_file_system(path, mode)?;
+    println!("Day 07 part 1: {:?}", sum_of_small_directories(&fs));
+
+    let needed = fs.needed_for_update(disk_space, update_size);
+    match fs.dir_to_delete_for_update(disk_space, update_size) {
+        Some(id) => println!("Day 07 part 2 {:?} ({})", fs.node(id).size, fs.path(id)),
+        None if needed == 0 => println!("Day 07 part 2: enough free space already"),
+        None => println!("Day 07 part 2: no directory frees {} bytes", needed),
+    }
+
+    Ok(())
+}
+
+pub fn day07() -> anyhow::Result<()> {
+    solve("data/day07.txt", Mode::Strict, DISK_SPACE, UPDATE_SIZE)
+}
+
+// Usage: day07 [--lenient] [--disk-space=N] [--update-size=N] [path]
+//        day07 tree [--json] [path]
+//        day07 du [--depth=N] [--json] [path]
+//        day07 find [--name=GLOB] [--min-size=N] [--max-size=N] [--type=f|d] [--json] [path]
+//        day07 top [--count=N] [--json] [path]
+//        day07 transcript (--disk=DIR | --seed=N [--dirs=N] [--files=N]) [--check]
+//        day07 shell [path]
+// Every command reading a transcript accepts --lenient to skip invalid lines with a warning
+pub fn cli(args: &[String]) -> anyhow::Result<()> {
+    let positionals = positionals(args);
+    let path = positionals.get(1).copied().unwrap_or("data/day07.txt");
+    let mode = match flag(args, "--lenient") {
+        true => Mode::Lenient,
+        false => Mode::Strict,
+    };
+    let format = match flag(args, "--json") {
+        true => Format::Json,
+        false => Format::Text,
+    };
+
+    match positionals.first().copied() {
+        Some("tree") => print!("{}", tree(&load_file_system(path, mode)?, format)),
+        Some("du") => {
+            let fs = load_file_system(path, mode)?;
+            let depth = option(args, "--depth=")?.unwrap_or(usize::MAX);
+            print!("{}", listing(&fs, &du(&fs, ROOT, depth), format));
+        }
+        Some("find") => {
+            let fs = load_file_system(path, mode)?;
+            let filter = Filter {
+                name: option(args, "--name=")?,
+                min_size: option(args, "--min-size=")?,
+                max_size: option(args, "--max-size=")?,
+                kind: match option::<String>(args, "--type=")?.as_deref() {
+                    Some("d") => Some(Kind::Directory),
+                    Some("f") => Some(Kind::File),
+                    Some(kind) => return Err(anyhow!("Unknown node type: {}", kind)),
+                    None => None,
+                },
+            };
+            print!("{}", listing(&fs, &find(&fs, &filter), format));
+        }
+        Some("top") => {
+            let fs = load_file_system(path, mode)?;
+            let count = option(args, "--count=")?.unwrap_or(10);
+            print!("{}", listing(&fs, &largest_directories(&fs, count), format));
+        }
+        Some("transcript") => {
+            let fs = match option::<String>(args, "--disk=")? {
+                Some(directory) => from_disk(std::path::Path::new(&directory))?,
+                None => random_file_system(
+                    option(args, "--seed=")?.unwrap_or(2022),
+                    option(args, "--dirs=")?.unwrap_or(100),
+                    option(args, "--files=")?.unwrap_or(1000),
+                ),
+            };
+            match flag(args, "--check") {
+                true => println!(
+                    "Day 07 transcript: {} nodes, {} bytes, sizes match",
+                    fs.descendants(ROOT).len(),
+                    check_round_trip(&fs)?.total_used()
+                ),
+                false => print!("{}", transcript(&fs)),
+            }
+        }
+        Some("shell") => {
+            let mut fs = match positionals.get(1) {
+                Some(path) => load_file_system(path, mode)?,
+                None => FileSystem::new(),
+            };
+            shell::shell(&mut fs, std::io::stdin().lock(), &mut std::io::stdout())?;
+        }
+        path => solve(
+            path.unwrap_or("data/day07.txt"),
+            mode,
+            option(args, "--disk-space=")?.unwrap_or(DISK_SPACE),
+            option(args, "--update-size=")?.unwrap_or(UPDATE_SIZE),
+        )?,
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn working_directory_should_be_expected() {
+        let mut fs = FileSystem::new();
+        fs.move_forward("a".to_string());
+        assert_eq!(fs.pwd(), "/a");
+    }
+
+    #[test]
+    fn working_directory_should_be_root() {
+        let fs = FileSystem::new();
+        assert_eq!(fs.pwd(), "/");
+    }
+
+    #[test]
+    fn working_directory_should_be_root_with_move_back_from_root() {
+        let mut fs = FileSystem::new();
+        fs.move_back();
+        assert_eq!(fs.pwd(), "/");
+    }
+
+    #[test]
+    fn working_directory_should_be_root_with_move_to_root() {
+        let mut fs = FileSystem::new();
+        fs.move_forward("a".to_string());
+        fs.move_to_root();
+        assert_eq!(fs.pwd(), "/");
+    }
+
+    #[test]
+    fn working_directory_should_follow_parent_links() {
+        let mut fs = FileSystem::new();
+        fs.move_forward("a".to_string());
+        fs.move_forward("e".to_string());
+        assert_eq!(fs.pwd(), "/a/e");
+        fs.move_back();
+        assert_eq!(fs.pwd(), "/a");
+    }
+
+    #[test]
+    fn root_should_exist() {
+        let fs = FileSystem::new();
+        let root = fs.node(ROOT);
+        assert_eq!(root.name, "");
+        assert_eq!(fs.path(ROOT), "/");
+        assert_eq!(fs.directories().count(), 1);
+    }
+
+    #[test]
+    fn mkdir_should_not_create_directory_if_already_exist() {
+        let mut fs = FileSystem::new();
+        let first = fs.create_directory_in_current_directory("a".to_string());
+        let second = fs.create_directory_in_current_directory("a".to_string());
+        assert_eq!(first, second);
+        assert_eq!(fs.node(first).name, "a");
+        assert_eq!(fs.path(first), "/a");
+        assert_eq!(fs.directories().count(), 2);
+    }
+
+    #[test]
+    fn directory_sizes_should_be_recursive() {
+        let mut fs = FileSystem::new();
+        fs.create_file_to_current_directory(File::new("b.txt".to_string(), 100));
+        fs.move_forward("a".to_string());
+        fs.create_file_to_current_directory(File::new("f".to_string(), 20));
+        fs.move_forward("e".to_string());
+        fs.create_file_to_current_directory(File::new("i".to_string(), 3));
+
+        let a = fs.child(ROOT, "a").unwrap();
+        let e = fs.child(a, "e").unwrap();
+        assert_eq!(fs.node(ROOT).size, 123);
+        assert_eq!(fs.node(a).size, 23);
+        assert_eq!(fs.node(e).size, 3);
+        assert_eq!(fs.node(e).parent, Some(a));
+    }
+
+    pub(super) const SAMPLE: &str = "$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+4060174 j
+8033020 d.log
+5626152 d.ext
+7214296 k
+";
+
+    #[test]
+    fn should_solve_sample() {
+        let fs = parse_file_system(SAMPLE.as_bytes()).unwrap();
+        assert_eq!(fs.total_used(), 48381165);
+        assert_eq!(fs.free(70000000), 21618835);
+        assert_eq!(sum_of_small_directories(&fs), 95437);
+
+        assert_eq!(fs.needed_for_update(DISK_SPACE, UPDATE_SIZE), 8381165);
+        let id = fs.dir_to_delete_for_update(DISK_SPACE, UPDATE_SIZE);
+        assert_eq!(id.map(|id| fs.path(id)), Some("/d".to_string()));
+        assert_eq!(id.map(|id| fs.node(id).size), Some(24933642));
+    }
+
+    #[test]
+    fn should_not_free_anything_with_enough_space() {
+        let fs = parse_file_system(SAMPLE.as_bytes()).unwrap();
+        assert_eq!(fs.needed_for_update(DISK_SPACE, 20000000), 0);
+        assert_eq!(fs.dir_to_delete_for_update(DISK_SPACE, 20000000), None);
+        assert_eq!(fs.needed_for_update(100000000, UPDATE_SIZE), 0);
+    }
+
+    #[test]
+    fn free_space_should_not_underflow() {
+        let fs = parse_file_system(SAMPLE.as_bytes()).unwrap();
+        assert_eq!(fs.free(1000), 0);
+        assert_eq!(fs.smallest_dir_to_free(fs.total_used() + 1), None);
+    }
+
+    #[test]
+    fn should_parse_file() {
+        assert_eq!(
+            "14848514 b.txt".parse::<File>().unwrap(),
+            File::new("b.txt".to_string(), 14848514)
+        );
+        assert_eq!(
+            "8504156 c.dat".parse::<File>().unwrap(),
+            File::new("c.dat".to_string(), 8504156)
+        );
+        assert_eq!(
+            "29116 f".parse::<File>().unwrap(),
+            File::new("f".to_string(), 29116)
+        );
+    }
+}
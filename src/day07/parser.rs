@@ -0,0 +1,234 @@
+use std::{collections::HashSet, io::BufRead};
+
+use crate::validation::{Mode, Warning};
+
+use super::{File, FileSystem, NodeId, NodeKind, ROOT};
+
+// Which command the listing lines being read belong to
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Output {
+    None,
+    Listing(NodeId),
+    // Output of a directory listed before, skipped so its files are not counted twice
+    Repeated,
+}
+
+struct Parser {
+    fs: FileSystem,
+    output: Output,
+    listed: HashSet<NodeId>,
+}
+
+impl Parser {
+    fn new() -> Self {
+        Self {
+            fs: FileSystem::new(),
+            output: Output::None,
+            listed: HashSet::new(),
+        }
+    }
+
+    fn cd(&mut self, name: &str) -> Result<(), String> {
+        self.output = Output::None;
+        let current = self.fs.working_directory;
+        match name {
+            "/" => self.fs.move_to_root(),
+            ".." if current == ROOT => return Err("cd .. from the root directory".to_string()),
+            ".." => self.fs.move_back(),
+            _ => match self.fs.child(current, name) {
+                Some(id) if !self.fs.node(id).is_directory() => {
+                    return Err(format!("cd into {}, which is a file", name));
+                }
+                Some(id) => self.fs.working_directory = id,
+                None => {
+                    // Created anyway, lenient mode reads the transcript as far as it can
+                    self.fs.move_forward(name.to_string());
+                    return Err(match self.listed.contains(&current) {
+                        true => {
+                            format!("cd into {}, not listed in {}", name, self.fs.path(current))
+                        }
+                        false => format!(
+                            "cd into {} before {} was listed",
+                            name,
+                            self.fs.path(current)
+                        ),
+                    });
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    fn ls(&mut self) -> Result<(), String> {
+        let current = self.fs.working_directory;
+        if !self.listed.insert(current) {
+            self.output = Output::Repeated;
+            return Err(format!(
+                "{} listed again, its output is ignored",
+                self.fs.path(current)
+            ));
+        }
+        self.output = Output::Listing(current);
+
+        Ok(())
+    }
+
+    fn entry(&mut self, line: &str) -> Result<(), String> {
+        let directory = match self.output {
+            Output::Listing(directory) => directory,
+            Output::Repeated => return Ok(()),
+            Output::None => return Err(format!("{:?} does not follow an ls", line)),
+        };
+
+        let (name, size, kind) = match line.strip_prefix("dir ") {
+            Some(name) if name.split_whitespace().count() == 1 => (
+                name.to_string(),
+                0,
+                NodeKind::Directory { children: vec![] },
+            ),
+            Some(_) => return Err(format!("invalid directory entry {:?}", line)),
+            None => {
+                let file = line.parse::<File>().map_err(|e| e.to_string())?;
+                (file.name, file.size, NodeKind::File)
+            }
+        };
+
+        // A directory may already exist when it was entered before being listed
+        match self.fs.child(directory, &name) {
+            Some(id) if self.fs.node(id).is_directory() && kind != NodeKind::File => Ok(()),
+            Some(_) => Err(format!(
+                "{} listed twice in {}",
+                name,
+                self.fs.path(directory)
+            )),
+            None => {
+                self.fs.add_node(directory, name, size, kind);
+                Ok(())
+            }
+        }
+    }
+
+    fn line(&mut self, line: &str) -> Result<(), String> {
+        let mut parts = line.split_whitespace();
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some("$"), Some("cd"), Some(name), None) => self.cd(name),
+            (Some("$"), Some("ls"), None, None) => self.ls(),
+            (Some("$"), _, _, _) => {
+                self.output = Output::None;
+                Err(format!("unknown command {:?}", line))
+            }
+            _ => self.entry(line),
+        }
+    }
+}
+
+pub(super) fn parse<R: BufRead>(
+    reader: R,
+    mode: Mode,
+) -> anyhow::Result<(FileSystem, Vec<Warning>)> {
+    let mut parser = Parser::new();
+    let mut warnings = vec![];
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Err(message) = parser.line(&line) {
+            let warning = Warning {
+                line: index + 1,
+                message,
+            };
+            mode.report(warning, &mut warnings)?;
+        }
+    }
+
+    Ok((parser.fs, warnings))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::tests::SAMPLE;
+    use super::*;
+
+    fn messages(input: &str) -> Vec<String> {
+        let (_, warnings) = parse(input.as_bytes(), Mode::Lenient).unwrap();
+        warnings.iter().map(|warning| warning.to_string()).collect()
+    }
+
+    #[test]
+    fn should_parse_sample_without_warning() {
+        let (fs, warnings) = parse(SAMPLE.as_bytes(), Mode::Strict).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(fs.total_used(), 48381165);
+    }
+
+    #[test]
+    fn should_not_count_repeated_listing_twice() {
+        let input = "$ cd /\n$ ls\n100 a\ndir b\n$ cd b\n$ ls\n10 c\n$ cd ..\n$ ls\n100 a\ndir b\n";
+        let (fs, warnings) = parse(input.as_bytes(), Mode::Lenient).unwrap();
+        assert_eq!(fs.total_used(), 110);
+        assert_eq!(
+            warnings.iter().map(|w| w.to_string()).collect::<Vec<_>>(),
+            vec!["line 9: / listed again, its output is ignored"]
+        );
+
+        let error = parse(input.as_bytes(), Mode::Strict).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 9: / listed again, its output is ignored"
+        );
+    }
+
+    #[test]
+    fn should_report_output_without_ls() {
+        assert_eq!(
+            messages("$ cd /\n100 a\n$ ls\n10 b\n$ cd ..\n"),
+            vec![
+                "line 2: \"100 a\" does not follow an ls",
+                "line 5: cd .. from the root directory"
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_cd_into_unlisted_directory() {
+        let input = "$ cd /\n$ cd a\n$ ls\n10 f\n$ cd ..\n$ ls\ndir b\n$ cd c\n$ ls\n5 g\n";
+        let (fs, warnings) = parse(input.as_bytes(), Mode::Lenient).unwrap();
+        assert_eq!(
+            warnings.iter().map(|w| w.to_string()).collect::<Vec<_>>(),
+            vec![
+                "line 2: cd into a before / was listed",
+                "line 8: cd into c, not listed in /"
+            ]
+        );
+        assert_eq!(fs.total_used(), 15);
+    }
+
+    #[test]
+    fn should_report_invalid_lines() {
+        assert_eq!(
+            messages("$ cd /\n$ ls\nabc f\n12\n1 2 3\ndir\n$ pwd\n5 f\n5 f\n$ cd f\n"),
+            vec![
+                "line 3: Invalid file size in \"abc f\"",
+                "line 4: Invalid file \"12\"",
+                "line 5: Invalid file \"1 2 3\"",
+                "line 6: Invalid file \"dir\"",
+                "line 7: unknown command \"$ pwd\"",
+                "line 8: \"5 f\" does not follow an ls",
+                "line 9: \"5 f\" does not follow an ls",
+                "line 10: cd into f, not listed in /",
+            ]
+        );
+        assert_eq!(
+            messages("$ ls\n5 f\n5 f\n$ cd f\n"),
+            vec![
+                "line 3: f listed twice in /",
+                "line 4: cd into f, which is a file"
+            ]
+        );
+    }
+}
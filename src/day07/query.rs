@@ -0,0 +1,360 @@
+use itertools::Itertools;
+
+use super::{FileSystem, NodeId, ROOT};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Kind {
+    Directory,
+    File,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct Filter {
+    pub(super) name: Option<String>,
+    pub(super) min_size: Option<u64>,
+    pub(super) max_size: Option<u64>,
+    pub(super) kind: Option<Kind>,
+}
+
+impl Filter {
+    fn matches(&self, fs: &FileSystem, id: NodeId) -> bool {
+        let node = fs.node(id);
+        let kind = match node.is_directory() {
+            true => Kind::Directory,
+            false => Kind::File,
+        };
+
+        self.name
+            .as_ref()
+            .is_none_or(|pattern| glob_match(pattern, &node.name))
+            && self.min_size.is_none_or(|min| node.size >= min)
+            && self.max_size.is_none_or(|max| node.size <= max)
+            && self.kind.is_none_or(|k| k == kind)
+    }
+}
+
+// Shell style pattern where `*` matches any run of characters and `?` a single one
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<char>>();
+    let name = name.chars().collect::<Vec<char>>();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    p = star + 1;
+                    n = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+
+    json
+}
+
+fn kind_name(fs: &FileSystem, id: NodeId) -> &'static str {
+    match fs.node(id).is_directory() {
+        true => "dir",
+        false => "file",
+    }
+}
+
+pub(super) fn sorted_children(fs: &FileSystem, id: NodeId) -> Vec<NodeId> {
+    fs.node(id)
+        .children()
+        .iter()
+        .copied()
+        .sorted_by(|a, b| fs.node(*a).name.cmp(&fs.node(*b).name))
+        .collect()
+}
+
+fn text_tree(fs: &FileSystem, id: NodeId, depth: usize, output: &mut String) {
+    let node = fs.node(id);
+    let name = match id {
+        ROOT => "/",
+        _ => node.name.as_str(),
+    };
+    output.push_str(&format!(
+        "{}- {} ({}, size={})\n",
+        "  ".repeat(depth),
+        name,
+        kind_name(fs, id),
+        node.size
+    ));
+    for child in sorted_children(fs, id) {
+        text_tree(fs, child, depth + 1, output);
+    }
+}
+
+fn json_tree(fs: &FileSystem, id: NodeId) -> String {
+    let node = fs.node(id);
+    let mut json = format!(
+        "{{\"name\":{},\"type\":\"{}\",\"size\":{}",
+        json_string(&node.name),
+        kind_name(fs, id),
+        node.size
+    );
+    if node.is_directory() {
+        json.push_str(&format!(
+            ",\"children\":[{}]",
+            sorted_children(fs, id)
+                .into_iter()
+                .map(|child| json_tree(fs, child))
+                .join(",")
+        ));
+    }
+    json.push('}');
+
+    json
+}
+
+// Every node with its recursive size, in the format of the puzzle statement
+pub(super) fn tree(fs: &FileSystem, format: Format) -> String {
+    match format {
+        Format::Text => {
+            let mut output = String::new();
+            text_tree(fs, ROOT, 0, &mut output);
+            output
+        }
+        Format::Json => format!("{}\n", json_tree(fs, ROOT)),
+    }
+}
+
+fn depth(fs: &FileSystem, id: NodeId) -> usize {
+    std::iter::successors(fs.node(id).parent, |&parent| fs.node(parent).parent).count()
+}
+
+// Directories down to `max_depth` below `from`, listed after their content like `du`
+pub(super) fn du(fs: &FileSystem, from: NodeId, max_depth: usize) -> Vec<NodeId> {
+    fn visit(fs: &FileSystem, id: NodeId, depth: usize, max_depth: usize, ids: &mut Vec<NodeId>) {
+        if depth < max_depth {
+            for child in sorted_children(fs, id) {
+                if fs.node(child).is_directory() {
+                    visit(fs, child, depth + 1, max_depth, ids);
+                }
+            }
+        }
+        ids.push(id);
+    }
+
+    let mut ids = vec![];
+    visit(fs, from, 0, max_depth, &mut ids);
+    ids
+}
+
+pub(super) fn find(fs: &FileSystem, filter: &Filter) -> Vec<NodeId> {
+    fs.descendants(ROOT)
+        .into_iter()
+        .filter(|&id| filter.matches(fs, id))
+        .sorted_by_key(|&id| fs.path(id))
+        .collect()
+}
+
+pub(super) fn largest_directories(fs: &FileSystem, count: usize) -> Vec<NodeId> {
+    fs.directories()
+        .sorted_by(|a, b| {
+            fs.node(*b)
+                .size
+                .cmp(&fs.node(*a).size)
+                .then_with(|| fs.path(*a).cmp(&fs.path(*b)))
+        })
+        .take(count)
+        .collect()
+}
+
+// Size in 1024 based units as `du -h` prints it, with a decimal below 10
+fn human_size(size: u64) -> String {
+    let mut value = size as f64;
+    let mut units = ["B", "K", "M", "G", "T"].iter().peekable();
+    // Values that would round up to 1024 move to the next unit
+    while value >= 1023.5 && units.len() > 1 {
+        value /= 1024.0;
+        units.next();
+    }
+
+    let unit = units.next().unwrap();
+    match (*unit, value) {
+        ("B", _) => format!("{}B", size),
+        (_, value) if value < 9.95 => format!("{:.1}{}", value, unit),
+        (_, value) => format!("{:.0}{}", value, unit),
+    }
+}
+
+// One line per node with its human readable size and path, or a JSON array of the nodes with
+// their sizes in bytes
+pub(super) fn listing(fs: &FileSystem, ids: &[NodeId], format: Format) -> String {
+    match format {
+        Format::Text => ids
+            .iter()
+            .map(|&id| format!("{}\t{}\n", human_size(fs.node(id).size), fs.path(id)))
+            .collect(),
+        Format::Json => format!(
+            "[{}]\n",
+            ids.iter()
+                .map(|&id| format!(
+                    "{{\"path\":{},\"type\":\"{}\",\"size\":{},\"depth\":{}}}",
+                    json_string(&fs.path(id)),
+                    kind_name(fs, id),
+                    fs.node(id).size,
+                    depth(fs, id)
+                ))
+                .join(",")
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::{parse_file_system, tests::SAMPLE};
+    use super::*;
+
+    fn sample() -> FileSystem {
+        parse_file_system(SAMPLE.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn should_match_glob() {
+        assert!(glob_match("*.txt", "b.txt"));
+        assert!(glob_match("d.*", "d.log"));
+        assert!(glob_match("?", "f"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("*.txt", "c.dat"));
+        assert!(!glob_match("?", "ab"));
+    }
+
+    #[test]
+    fn should_print_tree() {
+        assert_eq!(
+            tree(&sample(), Format::Text),
+            "- / (dir, size=48381165)
+  - a (dir, size=94853)
+    - e (dir, size=584)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir, size=24933642)
+    - d.ext (file, size=5626152)
+    - d.log (file, size=8033020)
+    - j (file, size=4060174)
+    - k (file, size=7214296)
+"
+        );
+    }
+
+    #[test]
+    fn should_print_tree_as_json() {
+        let json = tree(&sample(), Format::Json);
+        assert!(json.starts_with(
+            "{\"name\":\"\",\"type\":\"dir\",\"size\":48381165,\"children\":[{\"name\":\"a\""
+        ));
+        assert!(json.contains("{\"name\":\"i\",\"type\":\"file\",\"size\":584}"));
+    }
+
+    #[test]
+    fn should_format_sizes_with_units() {
+        assert_eq!(human_size(0), "0B");
+        assert_eq!(human_size(1023), "1023B");
+        assert_eq!(human_size(1024), "1.0K");
+        assert_eq!(human_size(2557), "2.5K");
+        assert_eq!(human_size(10239), "10K");
+        assert_eq!(human_size(94853), "93K");
+        assert_eq!(human_size(1024 * 1024 - 1), "1.0M");
+        assert_eq!(human_size(24933642), "24M");
+        assert_eq!(human_size(70000000000), "65G");
+        assert_eq!(human_size(u64::MAX), "16777216T");
+    }
+
+    #[test]
+    fn should_summarise_directories_by_depth() {
+        let fs = sample();
+        assert_eq!(listing(&fs, &du(&fs, ROOT, 0), Format::Text), "46M\t/\n");
+        assert_eq!(
+            listing(&fs, &du(&fs, ROOT, 1), Format::Text),
+            "93K\t/a\n24M\t/d\n46M\t/\n"
+        );
+        assert_eq!(du(&fs, ROOT, 5).len(), 4);
+    }
+
+    #[test]
+    fn should_find_nodes() {
+        let fs = sample();
+        let paths = |filter: Filter| {
+            find(&fs, &filter)
+                .into_iter()
+                .map(|id| fs.path(id))
+                .collect::<Vec<String>>()
+        };
+
+        assert_eq!(
+            paths(Filter {
+                name: Some("d.*".to_string()),
+                ..Filter::default()
+            }),
+            vec!["/d/d.ext", "/d/d.log"]
+        );
+        assert_eq!(
+            paths(Filter {
+                min_size: Some(100000),
+                kind: Some(Kind::Directory),
+                ..Filter::default()
+            }),
+            vec!["/", "/d"]
+        );
+        assert_eq!(
+            paths(Filter {
+                max_size: Some(3000),
+                ..Filter::default()
+            }),
+            vec!["/a/e", "/a/e/i", "/a/g"]
+        );
+    }
+
+    #[test]
+    fn should_list_largest_directories() {
+        let fs = sample();
+        assert_eq!(
+            listing(&fs, &largest_directories(&fs, 2), Format::Json),
+            "[{\"path\":\"/\",\"type\":\"dir\",\"size\":48381165,\"depth\":0},\
+             {\"path\":\"/d\",\"type\":\"dir\",\"size\":24933642,\"depth\":1}]\n"
+        );
+        assert_eq!(largest_directories(&fs, 10).len(), 4);
+    }
+}
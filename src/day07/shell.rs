@@ -0,0 +1,209 @@
+use anyhow::anyhow;
+use std::io::{BufRead, Write};
+
+use super::query::{du, listing, sorted_children, Format};
+use super::transcript::transcript;
+use super::{File, FileSystem, NodeId};
+
+fn lookup(fs: &FileSystem, name: &str) -> anyhow::Result<NodeId> {
+    fs.child(fs.working_directory, name)
+        .ok_or_else(|| anyhow!("{}: no such file or directory", name))
+}
+
+fn check_name(name: &str) -> anyhow::Result<()> {
+    match name {
+        "" | "." | ".." | "/" => Err(anyhow!("{}: invalid name", name)),
+        name if name.contains('/') => Err(anyhow!("{}: invalid name", name)),
+        _ => Ok(()),
+    }
+}
+
+fn cd(fs: &mut FileSystem, name: &str) -> anyhow::Result<()> {
+    match name {
+        "/" => fs.move_to_root(),
+        ".." => fs.move_back(),
+        name => {
+            let id = lookup(fs, name)?;
+            if !fs.node(id).is_directory() {
+                return Err(anyhow!("{}: not a directory", name));
+            }
+            fs.working_directory = id;
+        }
+    }
+
+    Ok(())
+}
+
+fn ls(fs: &FileSystem) -> String {
+    sorted_children(fs, fs.working_directory)
+        .into_iter()
+        .map(|id| {
+            let node = fs.node(id);
+            match node.is_directory() {
+                true => format!("dir {}\n", node.name),
+                false => format!("{} {}\n", node.size, node.name),
+            }
+        })
+        .collect()
+}
+
+fn mkdir(fs: &mut FileSystem, name: &str) -> anyhow::Result<()> {
+    check_name(name)?;
+    if fs.child(fs.working_directory, name).is_some() {
+        return Err(anyhow!("{}: already exists", name));
+    }
+    fs.create_directory_in_current_directory(name.to_string());
+
+    Ok(())
+}
+
+// Touching an existing file changes its size
+fn touch(fs: &mut FileSystem, size: &str, name: &str) -> anyhow::Result<()> {
+    check_name(name)?;
+    let size = size
+        .parse::<u64>()
+        .map_err(|_| anyhow!("{}: invalid size", size))?;
+    if let Some(id) = fs.child(fs.working_directory, name) {
+        if fs.node(id).is_directory() {
+            return Err(anyhow!("{}: is a directory", name));
+        }
+        fs.remove(id);
+    }
+    fs.create_file_to_current_directory(File::new(name.to_string(), size));
+
+    Ok(())
+}
+
+// Removing a directory removes everything below it
+fn rm(fs: &mut FileSystem, name: &str) -> anyhow::Result<()> {
+    let id = lookup(fs, name)?;
+    fs.remove(id);
+
+    Ok(())
+}
+
+fn run_command<W: Write>(fs: &mut FileSystem, line: &str, output: &mut W) -> anyhow::Result<()> {
+    let mut parts = line.split_whitespace();
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (None, _, _, _) => {}
+        (Some("cd"), Some(name), None, None) => cd(fs, name)?,
+        (Some("ls"), None, None, None) => write!(output, "{}", ls(fs))?,
+        (Some("pwd"), None, None, None) => writeln!(output, "{}", fs.pwd())?,
+        (Some("du"), depth, None, None) => {
+            let depth = match depth {
+                Some(depth) => depth.parse::<usize>()?,
+                None => 0,
+            };
+            let ids = du(fs, fs.working_directory, depth);
+            write!(output, "{}", listing(fs, &ids, Format::Text))?;
+        }
+        (Some("mkdir"), Some(name), None, None) => mkdir(fs, name)?,
+        (Some("touch"), Some(size), Some(name), None) => touch(fs, size, name)?,
+        (Some("rm"), Some(name), None, None) => rm(fs, name)?,
+        (Some("export"), Some(path), None, None) => std::fs::write(path, transcript(fs))?,
+        _ => writeln!(
+            output,
+            "commands: cd <dir>, ls, pwd, du [depth], mkdir <dir>, touch <size> <file>, rm <name>, export <path>, exit"
+        )?,
+    }
+
+    Ok(())
+}
+
+// The session starts from the root whatever the last `cd` of the loaded transcript was
+pub(super) fn shell<R: BufRead, W: Write>(
+    fs: &mut FileSystem,
+    input: R,
+    output: &mut W,
+) -> anyhow::Result<()> {
+    fs.move_to_root();
+    write!(output, "{}$ ", fs.pwd())?;
+    output.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        if matches!(line.trim(), "exit" | "quit") {
+            break;
+        }
+
+        if let Err(e) = run_command(fs, &line, output) {
+            writeln!(output, "error: {}", e)?;
+        }
+        write!(output, "{}$ ", fs.pwd())?;
+        output.flush()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::{parse_file_system, tests::SAMPLE, ROOT};
+    use super::*;
+
+    fn run(fs: &mut FileSystem, commands: &str) -> String {
+        let mut output = vec![];
+        shell(fs, commands.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn should_navigate_and_list() {
+        let mut fs = parse_file_system(SAMPLE.as_bytes()).unwrap();
+        assert_eq!(
+            run(&mut fs, "cd a\nls\ncd e\npwd\ncd /\ncd b.txt\ncd x\n"),
+            "/$ /a$ dir e\n29116 f\n2557 g\n62596 h.lst\n/a$ /a/e$ /a/e\n/a/e$ /$ \
+             error: b.txt: not a directory\n/$ error: x: no such file or directory\n/$ "
+        );
+    }
+
+    #[test]
+    fn should_keep_sizes_consistent() {
+        let mut fs = parse_file_system(SAMPLE.as_bytes()).unwrap();
+        run(
+            &mut fs,
+            "cd a\nmkdir new\ncd new\ntouch 1000 x\ntouch 10 y\ntouch 400 x\ncd /\nrm d\n",
+        );
+
+        let a = fs.child(ROOT, "a").unwrap();
+        let new = fs.child(a, "new").unwrap();
+        assert_eq!(fs.node(new).size, 410);
+        assert_eq!(fs.node(a).size, 94853 + 410);
+        assert_eq!(fs.total_used(), 48381165 - 24933642 + 410);
+        assert_eq!(fs.child(ROOT, "d"), None);
+        assert_eq!(fs.directories().count(), 4);
+    }
+
+    #[test]
+    fn should_reject_invalid_changes() {
+        let mut fs = parse_file_system(SAMPLE.as_bytes()).unwrap();
+        let output = run(
+            &mut fs,
+            "mkdir a\ntouch 10 a\ntouch ten z\nmkdir ..\nrm zz\n",
+        );
+        assert_eq!(output.matches("error:").count(), 5);
+        assert_eq!(fs.total_used(), 48381165);
+    }
+
+    #[test]
+    fn du_should_start_from_working_directory() {
+        let mut fs = parse_file_system(SAMPLE.as_bytes()).unwrap();
+        assert_eq!(
+            run(&mut fs, "cd a\ndu\ndu 1\n"),
+            "/$ /a$ 93K\t/a\n/a$ 584B\t/a/e\n93K\t/a\n/a$ "
+        );
+    }
+
+    #[test]
+    fn session_should_export_a_transcript() {
+        let mut fs = parse_file_system(SAMPLE.as_bytes()).unwrap();
+        run(
+            &mut fs,
+            "cd d\nrm k\nmkdir logs\ncd logs\ntouch 77 today.log\n",
+        );
+
+        let exported = parse_file_system(transcript(&fs).as_bytes()).unwrap();
+        assert_eq!(exported.total_used(), fs.total_used());
+        assert_eq!(transcript(&exported), transcript(&fs));
+    }
+}
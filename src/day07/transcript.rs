@@ -0,0 +1,214 @@
+use anyhow::anyhow;
+use std::{collections::BTreeMap, fs, path::Path};
+
+use crate::random::Random;
+
+use super::query::sorted_children;
+use super::{parse_file_system, File, FileSystem, NodeId, NodeKind, ROOT};
+
+fn write_directory(fs: &FileSystem, id: NodeId, output: &mut String) {
+    output.push_str("$ ls\n");
+    let children = sorted_children(fs, id);
+    for &child in children.iter() {
+        let node = fs.node(child);
+        match node.is_directory() {
+            true => output.push_str(&format!("dir {}\n", node.name)),
+            false => output.push_str(&format!("{} {}\n", node.size, node.name)),
+        }
+    }
+
+    for &child in children
+        .iter()
+        .filter(|&&child| fs.node(child).is_directory())
+    {
+        output.push_str(&format!("$ cd {}\n", fs.node(child).name));
+        write_directory(fs, child, output);
+        output.push_str("$ cd ..\n");
+    }
+}
+
+// Terminal session listing every directory once, that `load_file_system` reads back
+pub(super) fn transcript(fs: &FileSystem) -> String {
+    let mut output = String::from("$ cd /\n");
+    write_directory(fs, ROOT, &mut output);
+
+    output
+}
+
+fn walk(path: &Path, fs: &mut FileSystem) -> anyhow::Result<()> {
+    let mut entries = fs::read_dir(path)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = entry
+            .file_name()
+            .into_string()
+            .map_err(|name| anyhow!("Invalid file name: {:?}", name))?;
+        if name.is_empty() || name.contains(char::is_whitespace) || name == ".." {
+            return Err(anyhow!(
+                "{} cannot be written in a transcript",
+                entry.path().display()
+            ));
+        }
+
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            fs.move_forward(name);
+            walk(&entry.path(), fs)?;
+            fs.move_back();
+        } else if file_type.is_file() {
+            fs.create_file_to_current_directory(File::new(name, entry.metadata()?.len()));
+        }
+    }
+
+    Ok(())
+}
+
+// Symbolic links and special files are left out
+pub(super) fn from_disk(path: &Path) -> anyhow::Result<FileSystem> {
+    let mut fs = FileSystem::new();
+    walk(path, &mut fs)?;
+
+    Ok(fs)
+}
+
+// The same seed always gives the same tree
+pub(super) fn random_file_system(seed: u64, directories: usize, files: usize) -> FileSystem {
+    let mut random = Random(seed.max(1));
+    let mut fs = FileSystem::new();
+    let mut directory_ids = vec![ROOT];
+
+    for index in 0..directories {
+        let parent = directory_ids[random.next(directory_ids.len() as u64) as usize];
+        let id = fs.add_node(
+            parent,
+            format!("d{}", index),
+            0,
+            NodeKind::Directory { children: vec![] },
+        );
+        directory_ids.push(id);
+    }
+    for index in 0..files {
+        let parent = directory_ids[random.next(directory_ids.len() as u64) as usize];
+        let size = random.next(500_000) + 1;
+        fs.add_node(parent, format!("f{}.dat", index), size, NodeKind::File);
+    }
+
+    fs
+}
+
+fn sizes_by_path(fs: &FileSystem) -> BTreeMap<String, u64> {
+    fs.descendants(ROOT)
+        .into_iter()
+        .map(|id| (fs.path(id), fs.node(id).size))
+        .collect()
+}
+
+// Parses the transcript of `fs` back and compares the size of every node
+pub(super) fn check_round_trip(fs: &FileSystem) -> anyhow::Result<FileSystem> {
+    let parsed = parse_file_system(transcript(fs).as_bytes())?;
+    let expected = sizes_by_path(fs);
+    let found = sizes_by_path(&parsed);
+
+    for (path, size) in expected.iter() {
+        match found.get(path) {
+            Some(found) if found == size => {}
+            Some(found) => {
+                return Err(anyhow!(
+                    "{}: expected {} bytes, found {}",
+                    path,
+                    size,
+                    found
+                ))
+            }
+            None => return Err(anyhow!("{}: missing after round trip", path)),
+        }
+    }
+    if let Some(path) = found.keys().find(|path| !expected.contains_key(*path)) {
+        return Err(anyhow!("{}: unexpected after round trip", path));
+    }
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::tests::SAMPLE;
+    use super::*;
+
+    #[test]
+    fn should_write_sample_transcript() {
+        let fs = parse_file_system(SAMPLE.as_bytes()).unwrap();
+        assert_eq!(
+            transcript(&fs),
+            "$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+5626152 d.ext
+8033020 d.log
+4060174 j
+7214296 k
+$ cd ..
+"
+        );
+    }
+
+    #[test]
+    fn random_trees_should_round_trip() {
+        for seed in 1..20 {
+            let fs = random_file_system(seed, 200, 1000);
+            let parsed = check_round_trip(&fs).unwrap();
+            assert_eq!(parsed.total_used(), fs.total_used());
+            assert_eq!(parsed.descendants(ROOT).len(), 1201);
+        }
+    }
+
+    #[test]
+    fn disk_directory_should_round_trip() {
+        let root = std::env::temp_dir().join(format!("day07-transcript-{}", std::process::id()));
+        fs::create_dir_all(root.join("a/e")).unwrap();
+        fs::create_dir_all(root.join("d")).unwrap();
+        fs::write(root.join("b.txt"), vec![0; 1200]).unwrap();
+        fs::write(root.join("a/f"), vec![0; 30]).unwrap();
+        fs::write(root.join("a/e/i"), vec![0; 4]).unwrap();
+        fs::write(root.join("d/j"), vec![0; 500]).unwrap();
+
+        let disk = from_disk(&root);
+        fs::remove_dir_all(&root).unwrap();
+
+        let disk = disk.unwrap();
+        let parsed = check_round_trip(&disk).unwrap();
+        assert_eq!(parsed.total_used(), 1734);
+        let a = parsed.child(ROOT, "a").unwrap();
+        assert_eq!(parsed.node(a).size, 34);
+    }
+
+    #[test]
+    fn should_reject_names_with_spaces() {
+        let root = std::env::temp_dir().join(format!("day07-spaces-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a b"), vec![0; 10]).unwrap();
+
+        let disk = from_disk(&root);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(disk.is_err());
+    }
+}
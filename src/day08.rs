@@ -1,156 +1,511 @@
-use std::{
-    fs::File,
-    io::{BufRead, BufReader},
-};
-
-fn load_trees(path: &str) -> anyhow::Result<Vec<Vec<i32>>> {
-    let mut trees: Vec<Vec<i32>> = vec![];
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    for line in reader.lines() {
-        let line = line?;
-        let row: Vec<i32> = line
-            .chars()
-            .map(|c| c.to_digit(10).unwrap() as i32)
-            .collect();
-
-        trees.push(row);
-    }
-
-    Ok(trees)
-}
-
-#[derive(Debug)]
-struct CrossnNeighbour {
-    up: Vec<i32>,
-    down: Vec<i32>,
-    left: Vec<i32>,
-    right: Vec<i32>,
-}
-
-fn get_cross_neighbour(trees: &[Vec<i32>], row: usize, col: usize) -> CrossnNeighbour {
-    CrossnNeighbour {
-        up: trees[0..row].iter().map(|r| r[col]).collect(),
-        down: trees[row + 1..].iter().map(|r| r[col]).collect(),
-        left: trees[row][0..col].to_vec(),
-        right: trees[row][col + 1..].to_vec(),
-    }
-}
-
-fn is_visible(tree: i32, other: i32) -> bool {
-    other >= tree
-}
-
-fn tree_is_visible(tree: i32, others: Vec<i32>) -> bool {
-    !others.iter().any(|t| is_visible(tree, *t))
-}
-
-fn calculate_scenic_score(size: i32, others: Vec<i32>) -> i32 {
-    let mut cpt = 0;
-    for other in others.iter() {
-        cpt += 1;
-        if is_visible(size, *other) {
-            break;
-        }
-    }
-
-    cpt
-}
-
-fn calculate_tree_scenic_score(tree: i32, cross_neighbour: CrossnNeighbour) -> i32 {
-    let mut cross_neighbour = cross_neighbour;
-    cross_neighbour.up.reverse();
-    cross_neighbour.left.reverse();
-    let mut cpt = 0;
-    cpt += calculate_scenic_score(tree, cross_neighbour.up);
-    cpt *= calculate_scenic_score(tree, cross_neighbour.left);
-    cpt *= calculate_scenic_score(tree, cross_neighbour.down);
-    cpt *= calculate_scenic_score(tree, cross_neighbour.right);
-
-    cpt
-}
-
-fn count_visible_trees(trees: &Vec<Vec<i32>>) -> i32 {
-    let mut count = 0;
-    for (row, line) in trees.iter().enumerate() {
-        if row == 0 || row == trees.len() - 1 {
-            count += line.len() as i32;
-            continue;
-        }
-        for (col, tree) in line.iter().enumerate() {
-            if col == 0 || col == line.len() - 1 {
-                count += 1;
-                continue;
-            }
-
-            let cross_neighbour = get_cross_neighbour(trees, row, col);
-
-            if tree_is_visible(*tree, cross_neighbour.up)
-                || tree_is_visible(*tree, cross_neighbour.down)
-                || tree_is_visible(*tree, cross_neighbour.left)
-                || tree_is_visible(*tree, cross_neighbour.right)
-            {
-                count += 1;
-                continue;
-            }
-        }
-    }
-
-    count
-}
-
-fn highest_scenic_score(trees: &[Vec<i32>]) -> i32 {
-    let mut highest = 0;
-    for (row, line) in trees.iter().enumerate() {
-        for (col, tree) in line.iter().enumerate() {
-            let cross_neighbour = get_cross_neighbour(trees, row, col);
-            let score = calculate_tree_scenic_score(*tree, cross_neighbour);
-            if score > highest {
-                highest = score;
-            }
-        }
-    }
-
-    highest
-}
-
-pub fn day08() -> anyhow::Result<()> {
-    let trees_v2 = load_trees("data/day08.txt")?;
-    println!("Day 08 part 1 - {}", count_visible_trees(&trees_v2));
-    println!("Day 08 part 2 - {}", highest_scenic_score(&trees_v2));
-
-    Ok(())
-}
-
-#[cfg(test)]
-mod tests {
-
-    use super::*;
-
-    #[test]
-    fn calculate_tree_scenic_score_with_sample() {
-        let score = calculate_tree_scenic_score(
-            5,
-            CrossnNeighbour {
-                up: vec![3],
-                down: vec![4, 5, 3],
-                left: vec![2, 5],
-                right: vec![1, 2],
-            },
-        );
-
-        assert_eq!(score, 4);
-
-        let score = calculate_tree_scenic_score(
-            5,
-            CrossnNeighbour {
-                up: vec![3, 5, 3],
-                down: vec![3],
-                left: vec![3, 3],
-                right: vec![4, 9],
-            },
-        );
-
-        assert_eq!(score, 8);
-    }
-}
+use anyhow::anyhow;
+use std::{cmp::Reverse, iter};
+
+mod placement;
+mod render;
+
+use placement::place;
+use render::{render, Layer};
+
+use crate::cli::{flag, option, positionals};
+use crate::grid::{Direction, Grid, Position};
+use crate::random::Random;
+
+fn load_trees(path: &str) -> anyhow::Result<Grid<u8>> {
+    Grid::parse_digits(&std::fs::read_to_string(path)?)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Blocking {
+    TallerOrEqual,
+    Taller,
+}
+
+// How far trees see, the puzzle rules by default: four directions, no range limit, the view
+// stops at the first tree at least as tall, and the observer is at the top of the tree
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rules {
+    diagonals: bool,
+    // Number of trees seen along a line at most
+    max_distance: usize,
+    blocking: Blocking,
+    // Added to the height of the tree the observer stands on
+    observer_height: i32,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            diagonals: false,
+            max_distance: usize::MAX,
+            blocking: Blocking::TallerOrEqual,
+            observer_height: 0,
+        }
+    }
+}
+
+impl Rules {
+    fn directions(&self) -> &'static [Direction] {
+        match self.diagonals {
+            true => &Direction::ALL,
+            false => &Direction::ORTHOGONAL,
+        }
+    }
+
+    // Whether `other` hides what is behind it from an observer on `tree`
+    fn blocks(&self, tree: u8, other: u8) -> bool {
+        let eye = tree as i32 + self.observer_height;
+        match self.blocking {
+            Blocking::TallerOrEqual => other as i32 >= eye,
+            Blocking::Taller => other as i32 > eye,
+        }
+    }
+}
+
+// Visibility and viewing distances of every tree, looking from one side at a time
+struct Survey {
+    visible: Grid<bool>,
+    scores: Grid<u64>,
+}
+
+impl Survey {
+    // Each line of sight is swept once from each end, every tree looking back at the ones
+    // already met. A tree hides every tree behind it that is not taller, so the stack only
+    // keeps the trees that can still block the view, in decreasing height: there are at
+    // most ten of them, and every tree is pushed and popped once.
+    fn new(trees: &Grid<u8>, rules: &Rules) -> Self {
+        let mut survey = Self {
+            visible: Grid::filled(trees.width(), trees.height(), false),
+            scores: Grid::filled(trees.width(), trees.height(), 1),
+        };
+
+        for &direction in rules.directions() {
+            let edge = direction.opposite().step();
+            let starts = trees
+                .positions()
+                .filter(|&position| trees.offset(position, edge).is_none())
+                .collect::<Vec<_>>();
+            for start in starts {
+                let line = iter::once(start).chain(trees.ray(start, direction));
+                survey.sweep(trees, rules, line);
+            }
+        }
+
+        survey
+    }
+
+    fn sweep(&mut self, trees: &Grid<u8>, rules: &Rules, line: impl Iterator<Item = Position>) {
+        let mut blocking: Vec<(u8, usize)> = vec![];
+        for (index, position) in line.enumerate() {
+            let tree = trees[position];
+            let blocker = blocking
+                .iter()
+                .rev()
+                .find(|&&(other, _)| rules.blocks(tree, other));
+            let distance = match blocker {
+                Some(&(_, blocker)) => index - blocker,
+                None => {
+                    // Seen from outside the forest, `index` trees away from the edge
+                    if index < rules.max_distance {
+                        self.visible[position] = true;
+                    }
+                    index
+                }
+            };
+            self.scores[position] *= distance.min(rules.max_distance) as u64;
+
+            while blocking.last().is_some_and(|&(other, _)| other <= tree) {
+                blocking.pop();
+            }
+            blocking.push((tree, index));
+        }
+    }
+
+    fn count_visible_trees(&self) -> usize {
+        self.visible.iter().filter(|&&visible| visible).count()
+    }
+
+    // First tree in reading order among those with the highest score
+    fn best_tree(&self) -> Option<Position> {
+        self.scores
+            .positions()
+            .min_by_key(|&position| Reverse(self.scores[position]))
+    }
+
+    fn highest_scenic_score(&self) -> u64 {
+        self.best_tree().map_or(0, |position| self.scores[position])
+    }
+}
+
+// Trees seen from `from` looking in `direction`, the one blocking the view included
+fn line_of_sight<'a>(
+    trees: &'a Grid<u8>,
+    rules: &'a Rules,
+    from: Position,
+    direction: Direction,
+) -> impl Iterator<Item = Position> + 'a {
+    let tree = trees[from];
+    let mut blocked = false;
+    trees
+        .ray(from, direction)
+        .take(rules.max_distance)
+        .take_while(move |&position| {
+            let visible = !blocked;
+            blocked = blocked || rules.blocks(tree, trees[position]);
+            visible
+        })
+}
+
+fn random_forest(seed: u64, size: usize) -> Grid<u8> {
+    let mut random = Random(seed.max(1));
+    let mut trees = Grid::filled(size, size, 0);
+    for position in trees.positions().collect::<Vec<_>>() {
+        trees[position] = random.next(10) as u8;
+    }
+
+    trees
+}
+
+fn solve(trees: &Grid<u8>, rules: &Rules) {
+    let survey = Survey::new(trees, rules);
+    println!("Day 08 part 1 - {}", survey.count_visible_trees());
+    println!("Day 08 part 2 - {}", survey.highest_scenic_score());
+}
+
+pub fn day08() -> anyhow::Result<()> {
+    solve(&load_trees("data/day08.txt")?, &Rules::default());
+
+    Ok(())
+}
+
+fn parse_rules(args: &[String]) -> anyhow::Result<Rules> {
+    let default = Rules::default();
+    Ok(Rules {
+        diagonals: match option::<usize>(args, "--directions=")? {
+            Some(4) | None => false,
+            Some(8) => true,
+            Some(count) => return Err(anyhow!("Trees look in 4 or 8 directions, not {}", count)),
+        },
+        max_distance: option(args, "--max-distance=")?.unwrap_or(default.max_distance),
+        blocking: match flag(args, "--see-through") {
+            true => Blocking::Taller,
+            false => default.blocking,
+        },
+        observer_height: option(args, "--observer-height=")?.unwrap_or(default.observer_height),
+    })
+}
+
+// Usage: day08 [path]
+//        day08 render [--layer=visibility|scores] [--ppm=FILE] [path]
+//        day08 place [--count=K] [--exact] [path]
+// All accept --random=SIZE [--seed=N] to use a generated forest instead of a file, and
+// viewpoint rules: [--directions=4|8] [--max-distance=N] [--see-through] [--observer-height=N]
+// where --see-through lets trees see past trees of their own height
+pub fn cli(args: &[String]) -> anyhow::Result<()> {
+    let positionals = positionals(args);
+    let rules = parse_rules(args)?;
+    let trees = match option::<usize>(args, "--random=")? {
+        Some(size) => random_forest(option(args, "--seed=")?.unwrap_or(2022), size),
+        None => {
+            let path = match positionals.first() {
+                Some(&"render" | &"place") => positionals.get(1),
+                _ => positionals.first(),
+            };
+            load_trees(path.copied().unwrap_or("data/day08.txt"))?
+        }
+    };
+
+    match positionals.first().copied() {
+        Some("render") => {
+            let layer = match option::<String>(args, "--layer=")?.as_deref() {
+                Some("visibility") | None => Layer::Visibility,
+                Some("scores") => Layer::Scores,
+                Some(layer) => return Err(anyhow!("Unknown layer: {}", layer)),
+            };
+            let image = render(&trees, &rules, &Survey::new(&trees, &rules), layer);
+            match option::<String>(args, "--ppm=")? {
+                Some(path) => std::fs::write(path, render::ppm(&image))?,
+                None => print!("{}", render::ansi(&trees, &image)),
+            }
+        }
+        Some("place") => {
+            let count = option(args, "--count=")?.unwrap_or(1);
+            let placement = place(&trees, &rules, count, flag(args, "--exact"))?;
+            for (row, col) in placement.observers.iter() {
+                println!("Day 08 treehouse at row {}, column {}", row, col);
+            }
+            println!(
+                "Day 08 coverage - {} of {} trees",
+                placement.coverage,
+                trees.width() * trees.height()
+            );
+        }
+        _ => solve(&trees, &rules),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::time::Instant;
+
+    // Previous implementation, kept to compare against
+    #[derive(Debug)]
+    struct CrossnNeighbour {
+        up: Vec<i32>,
+        down: Vec<i32>,
+        left: Vec<i32>,
+        right: Vec<i32>,
+    }
+
+    fn get_cross_neighbour(trees: &[Vec<i32>], row: usize, col: usize) -> CrossnNeighbour {
+        CrossnNeighbour {
+            up: trees[0..row].iter().map(|r| r[col]).collect(),
+            down: trees[row + 1..].iter().map(|r| r[col]).collect(),
+            left: trees[row][0..col].to_vec(),
+            right: trees[row][col + 1..].to_vec(),
+        }
+    }
+
+    fn is_visible(tree: i32, other: i32) -> bool {
+        other >= tree
+    }
+
+    fn tree_is_visible(tree: i32, others: Vec<i32>) -> bool {
+        !others.iter().any(|t| is_visible(tree, *t))
+    }
+
+    fn calculate_scenic_score(size: i32, others: Vec<i32>) -> i32 {
+        let mut cpt = 0;
+        for other in others.iter() {
+            cpt += 1;
+            if is_visible(size, *other) {
+                break;
+            }
+        }
+
+        cpt
+    }
+
+    fn calculate_tree_scenic_score(tree: i32, cross_neighbour: CrossnNeighbour) -> i32 {
+        let mut cross_neighbour = cross_neighbour;
+        cross_neighbour.up.reverse();
+        cross_neighbour.left.reverse();
+        let mut cpt = 0;
+        cpt += calculate_scenic_score(tree, cross_neighbour.up);
+        cpt *= calculate_scenic_score(tree, cross_neighbour.left);
+        cpt *= calculate_scenic_score(tree, cross_neighbour.down);
+        cpt *= calculate_scenic_score(tree, cross_neighbour.right);
+
+        cpt
+    }
+
+    fn naive_count_visible_trees(trees: &[Vec<i32>]) -> i32 {
+        let mut count = 0;
+        for (row, line) in trees.iter().enumerate() {
+            if row == 0 || row == trees.len() - 1 {
+                count += line.len() as i32;
+                continue;
+            }
+            for (col, tree) in line.iter().enumerate() {
+                if col == 0 || col == line.len() - 1 {
+                    count += 1;
+                    continue;
+                }
+
+                let cross_neighbour = get_cross_neighbour(trees, row, col);
+
+                if tree_is_visible(*tree, cross_neighbour.up)
+                    || tree_is_visible(*tree, cross_neighbour.down)
+                    || tree_is_visible(*tree, cross_neighbour.left)
+                    || tree_is_visible(*tree, cross_neighbour.right)
+                {
+                    count += 1;
+                    continue;
+                }
+            }
+        }
+
+        count
+    }
+
+    fn naive_highest_scenic_score(trees: &[Vec<i32>]) -> i32 {
+        let mut highest = 0;
+        for (row, line) in trees.iter().enumerate() {
+            for (col, tree) in line.iter().enumerate() {
+                let cross_neighbour = get_cross_neighbour(trees, row, col);
+                let score = calculate_tree_scenic_score(*tree, cross_neighbour);
+                if score > highest {
+                    highest = score;
+                }
+            }
+        }
+
+        highest
+    }
+
+    pub(super) const SAMPLE: &str = "30373\n25512\n65332\n33549\n35390";
+
+    fn rows(trees: &Grid<u8>) -> Vec<Vec<i32>> {
+        (0..trees.height())
+            .map(|row| trees.row(row).map(|&tree| tree as i32).collect())
+            .collect()
+    }
+
+    #[test]
+    fn should_solve_sample() {
+        let survey = Survey::new(&Grid::parse_digits(SAMPLE).unwrap(), &Rules::default());
+        assert_eq!(survey.count_visible_trees(), 21);
+        assert_eq!(survey.highest_scenic_score(), 8);
+    }
+
+    #[test]
+    fn should_handle_degenerate_forests() {
+        assert_eq!(
+            Survey::new(&Grid::parse_digits("").unwrap(), &Rules::default()).count_visible_trees(),
+            0
+        );
+        assert_eq!(
+            Survey::new(&Grid::parse_digits("").unwrap(), &Rules::default()).highest_scenic_score(),
+            0
+        );
+        assert_eq!(
+            Survey::new(&Grid::parse_digits("5").unwrap(), &Rules::default()).count_visible_trees(),
+            1
+        );
+        assert_eq!(
+            Survey::new(&Grid::parse_digits("5").unwrap(), &Rules::default())
+                .highest_scenic_score(),
+            0
+        );
+        assert_eq!(
+            Survey::new(&Grid::parse_digits("101").unwrap(), &Rules::default())
+                .count_visible_trees(),
+            3
+        );
+    }
+
+    #[test]
+    fn should_match_naive_implementation() {
+        for seed in 1..200 {
+            let trees = random_forest(seed, (seed % 12) as usize + 1);
+            let survey = Survey::new(&trees, &Rules::default());
+            assert_eq!(
+                survey.count_visible_trees(),
+                naive_count_visible_trees(&rows(&trees)) as usize
+            );
+            assert_eq!(
+                survey.highest_scenic_score(),
+                naive_highest_scenic_score(&rows(&trees)) as u64
+            );
+        }
+    }
+
+    // Every tree looked at on its own, to check the sweeps against
+    fn naive_survey(trees: &Grid<u8>, rules: &Rules) -> Survey {
+        let mut survey = Survey {
+            visible: Grid::filled(trees.width(), trees.height(), false),
+            scores: Grid::filled(trees.width(), trees.height(), 1),
+        };
+        for position in trees.positions() {
+            for &direction in rules.directions() {
+                let seen = line_of_sight(trees, rules, position, direction).count();
+                let to_edge = trees.ray(position, direction).count();
+                let blocked = trees
+                    .ray(position, direction)
+                    .any(|other| rules.blocks(trees[position], trees[other]));
+                if !blocked && to_edge < rules.max_distance {
+                    survey.visible[position] = true;
+                }
+                survey.scores[position] *= seen as u64;
+            }
+        }
+
+        survey
+    }
+
+    #[test]
+    fn should_apply_viewpoint_rules_to_sample() {
+        let trees = Grid::parse_digits(SAMPLE).unwrap();
+        let survey = |rules: Rules| {
+            let survey = Survey::new(&trees, &rules);
+            (survey.count_visible_trees(), survey.highest_scenic_score())
+        };
+
+        let short_sighted = Rules {
+            max_distance: 1,
+            ..Rules::default()
+        };
+        assert_eq!(survey(short_sighted), (16, 1));
+
+        let on_a_ladder = Rules {
+            observer_height: 10,
+            ..Rules::default()
+        };
+        assert_eq!(survey(on_a_ladder), (25, 16));
+
+        let see_through = Rules {
+            blocking: Blocking::Taller,
+            observer_height: -1,
+            ..Rules::default()
+        };
+        assert_eq!(survey(see_through), survey(Rules::default()));
+
+        let diagonals = Rules {
+            diagonals: true,
+            ..Rules::default()
+        };
+        assert_eq!(survey(diagonals), (22, 16));
+    }
+
+    #[test]
+    fn sweeps_should_match_naive_survey_under_any_rules() {
+        let mut random = Random(42);
+        for seed in 1..300 {
+            let trees = random_forest(seed, (seed % 9) as usize + 1);
+            let rules = Rules {
+                diagonals: random.next(2) == 0,
+                max_distance: match random.next(3) {
+                    0 => usize::MAX,
+                    _ => random.next(5) as usize,
+                },
+                blocking: match random.next(2) {
+                    0 => Blocking::Taller,
+                    _ => Blocking::TallerOrEqual,
+                },
+                observer_height: random.next(5) as i32 - 2,
+            };
+            let survey = Survey::new(&trees, &rules);
+            let naive = naive_survey(&trees, &rules);
+            assert_eq!(survey.visible, naive.visible, "{:?}", rules);
+            assert_eq!(survey.scores, naive.scores, "{:?}", rules);
+        }
+    }
+
+    // cargo test --release -- --ignored --nocapture day08
+    #[test]
+    #[ignore]
+    fn bench_survey_on_large_forest() {
+        let trees = random_forest(2022, 5000);
+
+        let start = Instant::now();
+        let survey = Survey::new(&trees, &Rules::default());
+        println!(
+            "5000x5000 forest: {} visible, best score {} in {:?}",
+            survey.count_visible_trees(),
+            survey.highest_scenic_score(),
+            start.elapsed()
+        );
+    }
+
+    #[test]
+    fn calculate_tree_scenic_score_with_sample() {
+        let survey = Survey::new(&Grid::parse_digits(SAMPLE).unwrap(), &Rules::default());
+        // The middle 5 of the second row, then the 5 of the fourth row
+        assert_eq!(survey.scores[(1, 2)], 4);
+        assert_eq!(survey.scores[(3, 2)], 8);
+    }
+}
@@ -0,0 +1,237 @@
+use anyhow::anyhow;
+
+use crate::grid::{Grid, Position};
+
+use super::{line_of_sight, Rules};
+
+// The exact search enumerates combinations, past this it takes too long on a puzzle forest
+pub(super) const MAX_EXACT_COUNT: usize = 4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Placement {
+    pub(super) observers: Vec<Position>,
+    pub(super) coverage: usize,
+}
+
+// Cells seen by an observer on a tree, its own cell included, as indices in reading order
+struct Candidate {
+    position: Position,
+    cells: Vec<usize>,
+}
+
+fn candidates(trees: &Grid<u8>, rules: &Rules) -> Vec<Candidate> {
+    let index = |(row, col): Position| row * trees.width() + col;
+    let mut candidates = trees
+        .positions()
+        .map(|position| {
+            let mut cells = rules
+                .directions()
+                .iter()
+                .flat_map(|&direction| line_of_sight(trees, rules, position, direction))
+                .map(index)
+                .collect::<Vec<usize>>();
+            // Rays in different directions never share a cell, so there are no duplicates
+            cells.push(index(position));
+            Candidate { position, cells }
+        })
+        .collect::<Vec<Candidate>>();
+    // Largest coverage first, ties in reading order
+    candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.cells.len()));
+
+    candidates
+}
+
+fn gain(covered: &[bool], candidate: &Candidate) -> usize {
+    candidate
+        .cells
+        .iter()
+        .filter(|&&cell| !covered[cell])
+        .count()
+}
+
+fn cover(covered: &mut [bool], candidate: &Candidate) {
+    for &cell in candidate.cells.iter() {
+        covered[cell] = true;
+    }
+}
+
+// Picks the observer adding the most new cells, `count` times
+fn greedy(candidates: &[Candidate], cells: usize, count: usize) -> Placement {
+    let mut covered = vec![false; cells];
+    let mut placement = Placement {
+        observers: vec![],
+        coverage: 0,
+    };
+
+    for _ in 0..count.min(candidates.len()) {
+        let Some((best, added)) = candidates
+            .iter()
+            .filter(|candidate| !placement.observers.contains(&candidate.position))
+            .map(|candidate| (candidate, gain(&covered, candidate)))
+            .max_by_key(|&(candidate, added)| (added, std::cmp::Reverse(candidate.position)))
+        else {
+            break;
+        };
+        cover(&mut covered, best);
+        placement.observers.push(best.position);
+        placement.coverage += added;
+    }
+
+    placement
+}
+
+struct Search<'a> {
+    candidates: &'a [Candidate],
+    // How many observers cover each cell
+    covered: Vec<u32>,
+    chosen: Vec<usize>,
+    best: Placement,
+}
+
+impl Search<'_> {
+    // Candidates are sorted by decreasing coverage, so the ones left after `first` add at
+    // most the coverage of `first` each, and the loop stops once that cannot beat the best
+    fn explore(&mut self, first: usize, coverage: usize, remaining: usize) {
+        if remaining == 0 || first == self.candidates.len() {
+            if coverage > self.best.coverage {
+                self.best = Placement {
+                    observers: self
+                        .chosen
+                        .iter()
+                        .map(|&index| self.candidates[index].position)
+                        .collect(),
+                    coverage,
+                };
+            }
+            return;
+        }
+
+        for index in first..self.candidates.len() {
+            let candidate = &self.candidates[index];
+            if coverage + candidate.cells.len() * remaining <= self.best.coverage {
+                break;
+            }
+
+            let mut added = 0;
+            for &cell in candidate.cells.iter() {
+                if self.covered[cell] == 0 {
+                    added += 1;
+                }
+                self.covered[cell] += 1;
+            }
+            self.chosen.push(index);
+            self.explore(index + 1, coverage + added, remaining - 1);
+            self.chosen.pop();
+            for &cell in candidate.cells.iter() {
+                self.covered[cell] -= 1;
+            }
+        }
+    }
+}
+
+// Branch and bound, starting from the greedy placement
+fn exact(candidates: &[Candidate], cells: usize, count: usize) -> Placement {
+    let mut search = Search {
+        candidates,
+        covered: vec![0; cells],
+        chosen: vec![],
+        best: greedy(candidates, cells, count),
+    };
+    search.explore(0, 0, count);
+
+    search.best
+}
+
+// Trees where `count` observers see the most cells together
+pub(super) fn place(
+    trees: &Grid<u8>,
+    rules: &Rules,
+    count: usize,
+    exact_search: bool,
+) -> anyhow::Result<Placement> {
+    let candidates = candidates(trees, rules);
+    let cells = trees.width() * trees.height();
+    match exact_search {
+        true if count > MAX_EXACT_COUNT => Err(anyhow!(
+            "Exact search is limited to {} observers, {} asked",
+            MAX_EXACT_COUNT,
+            count
+        )),
+        true => Ok(exact(&candidates, cells, count)),
+        false => Ok(greedy(&candidates, cells, count)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::{random_forest, tests::SAMPLE};
+    use super::*;
+    use crate::random::Random;
+    use itertools::Itertools;
+
+    fn coverage(trees: &Grid<u8>, rules: &Rules, observers: &[Position]) -> usize {
+        let candidates = candidates(trees, rules);
+        let mut covered = vec![false; trees.width() * trees.height()];
+        for candidate in candidates
+            .iter()
+            .filter(|candidate| observers.contains(&candidate.position))
+        {
+            cover(&mut covered, candidate);
+        }
+
+        covered.iter().filter(|&&covered| covered).count()
+    }
+
+    fn brute_force(trees: &Grid<u8>, rules: &Rules, count: usize) -> usize {
+        trees
+            .positions()
+            .combinations(count.min(trees.width() * trees.height()))
+            .map(|observers| coverage(trees, rules, &observers))
+            .max()
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn should_place_one_observer_on_the_widest_view() {
+        let trees = Grid::parse_digits(SAMPLE).unwrap();
+        let rules = Rules::default();
+        let placement = place(&trees, &rules, 1, false).unwrap();
+        assert_eq!(placement.observers.len(), 1);
+        assert_eq!(placement.coverage, brute_force(&trees, &rules, 1));
+        assert_eq!(
+            placement.coverage,
+            coverage(&trees, &rules, &placement.observers)
+        );
+        assert_eq!(place(&trees, &rules, 1, true).unwrap(), placement);
+    }
+
+    #[test]
+    fn should_cover_the_whole_forest_with_enough_observers() {
+        let trees = Grid::parse_digits(SAMPLE).unwrap();
+        let placement = place(&trees, &Rules::default(), 25, false).unwrap();
+        assert_eq!(placement.coverage, 25);
+        assert!(placement.observers.len() <= 25);
+        assert!(place(&trees, &Rules::default(), 5, true).is_err());
+    }
+
+    #[test]
+    fn exact_search_should_match_brute_force() {
+        let mut random = Random(7);
+        for seed in 1..40 {
+            let trees = random_forest(seed, (seed % 4) as usize + 2);
+            let rules = Rules {
+                diagonals: random.next(2) == 0,
+                max_distance: random.next(3) as usize + 1,
+                ..Rules::default()
+            };
+            for count in 1..=3 {
+                let greedy = place(&trees, &rules, count, false).unwrap();
+                let exact = place(&trees, &rules, count, true).unwrap();
+                assert_eq!(exact.coverage, brute_force(&trees, &rules, count));
+                assert_eq!(exact.coverage, coverage(&trees, &rules, &exact.observers));
+                assert!(greedy.coverage <= exact.coverage);
+            }
+        }
+    }
+}
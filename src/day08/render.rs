@@ -0,0 +1,157 @@
+use crate::grid::Grid;
+
+use super::{line_of_sight, Rules, Survey};
+
+pub(super) type Colour = (u8, u8, u8);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Layer {
+    Visibility,
+    Scores,
+}
+
+const VISIBLE: Colour = (46, 160, 67);
+const HIDDEN: Colour = (48, 54, 61);
+const BEST_TREE: Colour = (255, 255, 255);
+const SIGHT_LINE: Colour = (255, 200, 0);
+const COLD: Colour = (20, 30, 110);
+const HOT: Colour = (240, 60, 30);
+
+fn blend(from: Colour, to: Colour, ratio: f64) -> Colour {
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * ratio).round() as u8;
+    (mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+}
+
+// Scores span several orders of magnitude, a logarithmic scale keeps the small ones apart
+fn heat(score: u64, highest: u64) -> Colour {
+    match highest {
+        0 => COLD,
+        _ => blend(COLD, HOT, (score as f64).ln_1p() / (highest as f64).ln_1p()),
+    }
+}
+
+// One colour per tree, the best tree and what it sees drawn over the layer
+pub(super) fn render(
+    trees: &Grid<u8>,
+    rules: &Rules,
+    survey: &Survey,
+    layer: Layer,
+) -> Grid<Colour> {
+    let highest = survey.highest_scenic_score();
+    let mut image = match layer {
+        Layer::Visibility => survey.visible.map(|&visible| match visible {
+            true => VISIBLE,
+            false => HIDDEN,
+        }),
+        Layer::Scores => survey.scores.map(|&score| heat(score, highest)),
+    };
+
+    if let Some(best) = survey.best_tree() {
+        for &direction in rules.directions() {
+            for position in line_of_sight(trees, rules, best, direction) {
+                image[position] = SIGHT_LINE;
+            }
+        }
+        image[best] = BEST_TREE;
+    }
+
+    image
+}
+
+// Tree heights on coloured backgrounds, for terminals supporting 24-bit colours
+pub(super) fn ansi(trees: &Grid<u8>, image: &Grid<Colour>) -> String {
+    let mut output = String::new();
+    for row in 0..trees.height() {
+        for (&tree, &colour) in trees.row(row).zip(image.row(row)) {
+            // Dark digits on light backgrounds
+            let foreground = match colour {
+                BEST_TREE | SIGHT_LINE => 30,
+                _ => 97,
+            };
+            output.push_str(&format!(
+                "\x1b[{};48;2;{};{};{}m{}",
+                foreground, colour.0, colour.1, colour.2, tree
+            ));
+        }
+        output.push_str("\x1b[0m\n");
+    }
+
+    output
+}
+
+// Binary portable pixmap, one pixel per tree
+pub(super) fn ppm(image: &Grid<Colour>) -> Vec<u8> {
+    let mut output = format!("P6\n{} {}\n255\n", image.width(), image.height()).into_bytes();
+    for &(r, g, b) in image.iter() {
+        output.extend([r, g, b]);
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::tests::SAMPLE;
+    use super::*;
+    use crate::grid::Direction;
+
+    fn sample() -> (Grid<u8>, Survey) {
+        let trees = Grid::parse_digits(SAMPLE).unwrap();
+        let survey = Survey::new(&trees, &Rules::default());
+        (trees, survey)
+    }
+
+    #[test]
+    fn should_highlight_best_tree_and_its_lines_of_sight() {
+        let (trees, survey) = sample();
+        let image = render(&trees, &Rules::default(), &survey, Layer::Visibility);
+        assert_eq!(survey.best_tree(), Some((3, 2)));
+        assert_eq!(image[(3, 2)], BEST_TREE);
+        for position in [(1, 2), (2, 2), (3, 0), (3, 1), (3, 3), (3, 4), (4, 2)] {
+            assert_eq!(image[position], SIGHT_LINE, "{:?}", position);
+        }
+        assert_eq!(image[(0, 0)], VISIBLE);
+        assert_eq!(image[(1, 3)], HIDDEN);
+        assert_eq!(image.iter().filter(|&&c| c == SIGHT_LINE).count(), 7);
+    }
+
+    #[test]
+    fn lines_of_sight_should_match_scenic_score() {
+        let (trees, survey) = sample();
+        for position in trees.positions() {
+            let score = Direction::ORTHOGONAL
+                .iter()
+                .map(|&direction| {
+                    line_of_sight(&trees, &Rules::default(), position, direction).count() as u64
+                })
+                .product::<u64>();
+            assert_eq!(score, survey.scores[position], "{:?}", position);
+        }
+    }
+
+    #[test]
+    fn should_colour_scores_on_a_log_scale() {
+        assert_eq!(heat(0, 8), COLD);
+        assert_eq!(heat(8, 8), HOT);
+        assert_eq!(heat(0, 0), COLD);
+        let (trees, survey) = sample();
+        let image = render(&trees, &Rules::default(), &survey, Layer::Scores);
+        assert_eq!(image[(0, 0)], COLD);
+        assert_eq!(image[(1, 1)], heat(1, 8));
+    }
+
+    #[test]
+    fn should_encode_images() {
+        let (trees, survey) = sample();
+        let image = render(&trees, &Rules::default(), &survey, Layer::Visibility);
+        let ppm = ppm(&image);
+        assert!(ppm.starts_with(b"P6\n5 5\n255\n"));
+        assert_eq!(ppm.len(), "P6\n5 5\n255\n".len() + 5 * 5 * 3);
+
+        let ansi = ansi(&trees, &image);
+        assert_eq!(ansi.lines().count(), 5);
+        assert!(ansi.starts_with("\x1b[97;48;2;46;160;67m3"));
+        assert!(ansi.lines().all(|line| line.ends_with("\x1b[0m")));
+    }
+}
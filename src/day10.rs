@@ -1,99 +1,394 @@
-use anyhow::anyhow;
-use std::{
-    collections::HashMap,
-    fs::File,
-    io::{BufRead, BufReader},
-    str::FromStr,
-};
-
-#[derive(Debug)]
-struct Instruction {
-    operation: String,
-    argument: Option<i32>,
-}
-
-impl FromStr for Instruction {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut split = s.split(" ");
-
-        match (split.next(), split.next(), split.next()) {
-            (Some("addx"), Some(argument), None) => Ok(Instruction {
-                operation: "addx".to_string(),
-                argument: Some(argument.parse::<i32>()?),
-            }),
-            (Some("noop"), None, None) => Ok(Instruction {
-                operation: "noop".to_string(),
-                argument: None,
-            }),
-            _ => Err(anyhow!("Invalid instruction")),
-        }
-    }
-}
-
-fn load_instructions(path: &str) -> anyhow::Result<Vec<Instruction>> {
-    BufReader::new(File::open(path)?)
-        .lines()
-        .map(|line| line?.parse::<Instruction>())
-        .collect()
-}
-
-type Register = i32;
-type Cycle = i32;
-type SignalStrength = i32;
-
-struct Cpu {
-    register: Register,
-    register_changes: HashMap<Cycle, Register>,
-}
-
-impl Cpu {
-    fn run_program(instructions: &[Instruction]) -> Self {
-        let mut register_changes: HashMap<Cycle, Register> = HashMap::new();
-        let mut register: Register = 1;
-        let mut cycles_count: Cycle = 1;
-
-        for instruction in instructions {
-            cycles_count += 1;
-            register_changes.insert(cycles_count as Cycle, register);
-            match instruction.operation.as_str() {
-                "addx" => {
-                    register += instruction.argument.unwrap();
-                    cycles_count += 1;
-                    register_changes.insert(cycles_count as Cycle, register);
-                }
-                "noop" => {}
-                _ => {}
-            }
-        }
-
-        Self {
-            register,
-            register_changes,
-        }
-    }
-
-    fn signal_of_cycle(&self, cycle: Cycle) -> SignalStrength {
-        match self.register_changes.get(&cycle) {
-            Some(signal) => signal.to_owned() * cycle,
-            None => 0,
-        }
-    }
-
-    fn sum_of_signal(&self) -> SignalStrength {
-        [20, 60, 100, 140, 180, 220]
-            .iter()
-            .map(|cycle| self.signal_of_cycle(*cycle))
-            .sum()
-    }
-}
-
-pub fn day10() -> anyhow::Result<()> {
-    let instructions = load_instructions("data/day10.txt")?;
-
-    let cpu = Cpu::run_program(&instructions);
-    println!("Day 10 part 1: {}", cpu.sum_of_signal());
-
-    Ok(())
-}
+use anyhow::anyhow;
+use std::ops::{Index, IndexMut};
+
+use crate::cli::positionals;
+
+mod assembler;
+mod crt;
+mod debugger;
+mod encoding;
+mod instruction;
+
+use crt::{Crt, HEIGHT, WIDTH};
+use instruction::{Op, Operand, Reg};
+
+type Register = i32;
+type Cycle = usize;
+type SignalStrength = i32;
+
+const INITIAL_REGISTER: Register = 1;
+
+// Programs are either assembly source or the output of `day10 assemble`
+fn load_program(path: &str) -> anyhow::Result<Vec<Op>> {
+    parse_program(std::fs::read(path)?)
+}
+
+fn parse_program(bytes: Vec<u8>) -> anyhow::Result<Vec<Op>> {
+    match encoding::is_encoded(&bytes) {
+        true => encoding::decode(&bytes),
+        false => assembler::assemble(&String::from_utf8(bytes)?),
+    }
+}
+
+// `x` starts at 1, the other registers at 0
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Registers([Register; Reg::ALL.len()]);
+
+impl Default for Registers {
+    fn default() -> Self {
+        let mut registers = Self([0; Reg::ALL.len()]);
+        registers[Reg::X] = INITIAL_REGISTER;
+        registers
+    }
+}
+
+impl Index<Reg> for Registers {
+    type Output = Register;
+
+    fn index(&self, reg: Reg) -> &Register {
+        &self.0[reg as usize]
+    }
+}
+
+impl IndexMut<Reg> for Registers {
+    fn index_mut(&mut self, reg: Reg) -> &mut Register {
+        &mut self.0[reg as usize]
+    }
+}
+
+impl Registers {
+    fn value(&self, operand: Operand) -> Register {
+        match operand {
+            Operand::Value(value) => value,
+            Operand::Reg(reg) => self[reg],
+        }
+    }
+}
+
+// Value of `x` while a cycle runs and once it is over
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Tick {
+    cycle: Cycle,
+    during: Register,
+    after: Register,
+}
+
+// Runs a program one cycle at a time, an instruction only takes effect at the end of its
+// last cycle. The program halts when the pointer leaves it. Registers are 32 bits wide and
+// arithmetic wraps around on overflow.
+struct Cpu<'a> {
+    program: &'a [Op],
+    pointer: usize,
+    // Cycles already spent on the current instruction
+    elapsed: usize,
+    cycle: Cycle,
+    registers: Registers,
+}
+
+impl<'a> Cpu<'a> {
+    fn new(program: &'a [Op]) -> Self {
+        Self {
+            program,
+            pointer: 0,
+            elapsed: 0,
+            cycle: 0,
+            registers: Registers::default(),
+        }
+    }
+
+    fn jump(&mut self, offset: isize) {
+        self.pointer = self
+            .pointer
+            .checked_add_signed(offset)
+            .unwrap_or(self.program.len());
+    }
+
+    fn execute(&mut self, op: Op) {
+        let registers = &mut self.registers;
+        match op {
+            Op::Noop => {}
+            Op::Add(reg, operand) => {
+                registers[reg] = registers[reg].wrapping_add(registers.value(operand))
+            }
+            Op::Sub(reg, operand) => {
+                registers[reg] = registers[reg].wrapping_sub(registers.value(operand))
+            }
+            Op::Mul(reg, operand) => {
+                registers[reg] = registers[reg].wrapping_mul(registers.value(operand))
+            }
+            Op::Set(reg, operand) => registers[reg] = registers.value(operand),
+            Op::Cmp(reg, operand) => {
+                registers[Reg::F] = registers[reg].cmp(&registers.value(operand)) as Register
+            }
+            Op::Jmp(offset) => return self.jump(offset),
+            Op::Jnz(operand, offset) if registers.value(operand) != 0 => return self.jump(offset),
+            Op::Jnz(..) => {}
+        }
+        self.pointer += 1;
+    }
+
+    fn tick(&mut self) -> Option<Tick> {
+        let op = *self.program.get(self.pointer)?;
+        self.cycle += 1;
+        self.elapsed += 1;
+        let during = self.registers[Reg::X];
+
+        if self.elapsed == op.cycles() {
+            self.execute(op);
+            self.elapsed = 0;
+        }
+
+        Some(Tick {
+            cycle: self.cycle,
+            during,
+            after: self.registers[Reg::X],
+        })
+    }
+}
+
+impl Iterator for Cpu<'_> {
+    type Item = Tick;
+
+    fn next(&mut self) -> Option<Tick> {
+        self.tick()
+    }
+}
+
+struct Trace {
+    ticks: Vec<Tick>,
+}
+
+impl Trace {
+    // Both parts only read the cycles of one screen, so programs that loop forever stop there
+    fn run_program(program: &[Op]) -> Self {
+        Self {
+            ticks: Cpu::new(program).take(WIDTH * HEIGHT).collect(),
+        }
+    }
+
+    // Cycles start at 1, once the program is over the register keeps its last value
+    fn register_during(&self, cycle: Cycle) -> Register {
+        match self.ticks.get(cycle.saturating_sub(1)) {
+            Some(tick) => tick.during,
+            None => self
+                .ticks
+                .last()
+                .map_or(INITIAL_REGISTER, |tick| tick.after),
+        }
+    }
+
+    fn signal_of_cycle(&self, cycle: Cycle) -> SignalStrength {
+        self.register_during(cycle)
+            .wrapping_mul(cycle as SignalStrength)
+    }
+
+    fn sum_of_signal(&self) -> SignalStrength {
+        [20, 60, 100, 140, 180, 220]
+            .iter()
+            .map(|cycle| self.signal_of_cycle(*cycle))
+            .fold(0, SignalStrength::wrapping_add)
+    }
+}
+
+fn solve(path: &str) -> anyhow::Result<()> {
+    let program = load_program(path)?;
+
+    let trace = Trace::run_program(&program);
+    println!("Day 10 part 1: {}", trace.sum_of_signal());
+
+    let crt = Crt::draw(&trace);
+    print!("{}", crt);
+    println!("Day 10 part 2: {}", crt.decode());
+
+    Ok(())
+}
+
+pub fn day10() -> anyhow::Result<()> {
+    solve("data/day10.txt")
+}
+
+// Usage: day10 [path]
+//        day10 debug [path]
+//        day10 assemble <source> <output>
+//        day10 disassemble <path>
+pub fn cli(args: &[String]) -> anyhow::Result<()> {
+    let positionals = positionals(args);
+    match positionals.first().copied() {
+        Some("debug") => {
+            let program = load_program(positionals.get(1).copied().unwrap_or("data/day10.txt"))?;
+            debugger::debug(&program, std::io::stdin().lock(), &mut std::io::stdout())
+        }
+        Some("assemble") => match positionals[1..] {
+            [source, output] => {
+                let bytes = encoding::encode(&load_program(source)?);
+                std::fs::write(output, &bytes)?;
+                println!("Day 10 assembled {} bytes into {}", bytes.len(), output);
+                Ok(())
+            }
+            _ => Err(anyhow!("Usage: day10 assemble <source> <output>")),
+        },
+        Some("disassemble") => match positionals[1..] {
+            [path] => {
+                print!("{}", assembler::disassemble(&load_program(path)?));
+                Ok(())
+            }
+            _ => Err(anyhow!("Usage: day10 disassemble <path>")),
+        },
+        path => solve(path.unwrap_or("data/day10.txt")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    pub(super) fn program(lines: &[&str]) -> Vec<Op> {
+        lines
+            .iter()
+            .map(|line| line.parse::<Op>().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn should_parse_ops() {
+        assert_eq!("noop".parse::<Op>().unwrap(), Op::Noop);
+        assert_eq!(
+            "addx -12".parse::<Op>().unwrap(),
+            Op::Add(Reg::X, Operand::Value(-12))
+        );
+        assert!("addx".parse::<Op>().is_err());
+        assert!("addx 1 2".parse::<Op>().is_err());
+        assert!("addx y".parse::<Op>().is_err());
+        assert!("divx 2".parse::<Op>().is_err());
+    }
+
+    #[test]
+    fn should_tick_one_cycle_at_a_time() {
+        let program = program(&["noop", "addx 3", "addx -5"]);
+        let ticks = Cpu::new(&program)
+            .map(|tick| (tick.cycle, tick.during, tick.after))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ticks,
+            vec![(1, 1, 1), (2, 1, 1), (3, 1, 4), (4, 4, 4), (5, 4, -1)]
+        );
+    }
+
+    #[test]
+    fn signal_should_hold_between_and_after_changes() {
+        let trace = Trace::run_program(&program(&["noop", "addx 3", "addx -5"]));
+        assert_eq!(trace.signal_of_cycle(1), 1);
+        assert_eq!(trace.signal_of_cycle(2), 2);
+        assert_eq!(trace.signal_of_cycle(4), 16);
+        assert_eq!(trace.signal_of_cycle(6), -6);
+        assert_eq!(trace.signal_of_cycle(20), -20);
+
+        let empty = Trace::run_program(&[]);
+        assert_eq!(empty.signal_of_cycle(20), 20);
+        assert_eq!(empty.sum_of_signal(), 720);
+    }
+
+    fn run(lines: &[&str]) -> (Registers, Cycle) {
+        let program = program(lines);
+        let mut cpu = Cpu::new(&program);
+        let cycles = cpu.by_ref().count();
+        (cpu.registers, cycles)
+    }
+
+    #[test]
+    fn should_parse_and_display_every_instruction() {
+        let lines = [
+            "noop", "addx 3", "subx a", "mulx -2", "setx 7", "add b 1", "sub c x", "mul d d",
+            "set a 10", "cmp a b", "jmp -3", "jnz f 2", "jnz 1 -1",
+        ];
+        for line in lines {
+            assert_eq!(line.parse::<Op>().unwrap().to_string(), line);
+        }
+        assert_eq!(
+            "add x 3".parse::<Op>().unwrap(),
+            "addx 3".parse::<Op>().unwrap()
+        );
+        assert!("cmpx 1".parse::<Op>().is_err());
+        assert!("jmp a".parse::<Op>().is_err());
+        assert!("set y 1".parse::<Op>().is_err());
+    }
+
+    #[test]
+    fn should_use_cycle_costs_of_the_table() {
+        assert_eq!(run(&["noop"]).1, 1);
+        assert_eq!(run(&["addx 1"]).1, 2);
+        assert_eq!(run(&["add a 1"]).1, 2);
+        assert_eq!(run(&["mulx 2"]).1, 4);
+        assert_eq!(run(&["setx 2", "cmp x 1", "jmp 1"]).1, 3);
+    }
+
+    #[test]
+    fn should_compute_on_every_register() {
+        let (registers, _) = run(&[
+            "set a 6", "mul a 7", "sub a 2", "setx a", "addx 5", "cmp x a",
+        ]);
+        assert_eq!(registers[Reg::A], 40);
+        assert_eq!(registers[Reg::X], 45);
+        assert_eq!(registers[Reg::F], 1);
+        assert_eq!(registers[Reg::B], 0);
+    }
+
+    #[test]
+    fn should_loop_with_relative_jumps() {
+        // x = 1 + 2 + ... + 5
+        let (registers, cycles) = run(&[
+            "set a 5", "setx 0", "add x a", "sub a 1", "jnz a -2", "jmp 2", "setx -1",
+        ]);
+        assert_eq!(registers[Reg::X], 15);
+        assert_eq!(registers[Reg::A], 0);
+        assert_eq!(cycles, 1 + 1 + 5 * (2 + 2 + 2) + 1);
+
+        // Jumping out of the program halts it
+        assert_eq!(run(&["jmp -1", "setx 2"]).0[Reg::X], 1);
+        assert_eq!(run(&["jmp 5", "setx 2"]).1, 1);
+    }
+
+    #[test]
+    fn should_tell_source_from_binary_programs() {
+        let source = "D10: noop\njmp D10\n";
+        let expected = vec![Op::Noop, Op::Jmp(-1)];
+        assert_eq!(parse_program(source.as_bytes().to_vec()).unwrap(), expected);
+        assert_eq!(
+            parse_program(encoding::encode(&expected)).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn should_stop_programs_looping_forever_after_one_screen() {
+        let trace = Trace::run_program(&program(&["addx 1", "jmp -1"]));
+        assert_eq!(trace.ticks.len(), WIDTH * HEIGHT);
+        assert_eq!(trace.signal_of_cycle(20), 7 * 20);
+    }
+
+    #[test]
+    fn arithmetic_should_wrap_around() {
+        let (registers, _) = run(&[
+            "set a 100000",
+            "mul a a",
+            "set b 2147483647",
+            "add b 1",
+            "setx -2147483648",
+            "cmp x 1",
+            "set c x",
+            "sub c 1",
+        ]);
+        assert_eq!(registers[Reg::A], 1410065408);
+        assert_eq!(registers[Reg::B], i32::MIN);
+        assert_eq!(registers[Reg::F], -1);
+        assert_eq!(registers[Reg::C], i32::MAX);
+
+        let mut lines = vec!["setx 100000000"];
+        lines.extend(["noop"; 20]);
+        let trace = Trace::run_program(&program(&lines));
+        assert_eq!(trace.signal_of_cycle(20), 100000000i32.wrapping_mul(20));
+        assert_eq!(trace.sum_of_signal(), 100000000i32.wrapping_mul(720));
+        assert_eq!(Crt::draw(&trace).to_string().matches('#').count(), 1);
+    }
+}
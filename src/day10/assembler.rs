@@ -0,0 +1,393 @@
+use anyhow::anyhow;
+use std::collections::{BTreeMap, HashMap};
+
+use super::instruction::{Op, Reg};
+use super::Register;
+
+// Macros may call other macros, but not endlessly
+const MAX_MACRO_DEPTH: usize = 16;
+
+type LineNumber = usize;
+
+struct Macro {
+    parameters: Vec<String>,
+    body: Vec<Vec<String>>,
+}
+
+#[derive(Default)]
+struct Assembler {
+    constants: HashMap<String, Register>,
+    macros: HashMap<String, Macro>,
+    // Index of the instruction following each label
+    labels: HashMap<String, usize>,
+    // Instructions once macros are expanded, with the line they come from
+    instructions: Vec<(LineNumber, Vec<String>)>,
+}
+
+fn check_symbol(name: &str) -> anyhow::Result<()> {
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    match valid && name.parse::<Reg>().is_err() {
+        true => Ok(()),
+        false => Err(anyhow!("Invalid name: {:?}", name)),
+    }
+}
+
+impl Assembler {
+    fn define_constant(&mut self, name: &str, value: &str) -> anyhow::Result<()> {
+        check_symbol(name)?;
+        let value = match self.constants.get(value) {
+            Some(&value) => value,
+            None => value
+                .parse::<Register>()
+                .map_err(|_| anyhow!("Invalid constant value: {:?}", value))?,
+        };
+        if self.constants.insert(name.to_string(), value).is_some() {
+            return Err(anyhow!("Constant {} defined twice", name));
+        }
+
+        Ok(())
+    }
+
+    fn define_label(&mut self, name: &str) -> anyhow::Result<()> {
+        check_symbol(name)?;
+        if self
+            .labels
+            .insert(name.to_string(), self.instructions.len())
+            .is_some()
+        {
+            return Err(anyhow!("Label {} defined twice", name));
+        }
+
+        Ok(())
+    }
+
+    // Expands macro calls, other statements are kept for the second pass
+    fn statement(
+        &mut self,
+        line: LineNumber,
+        tokens: Vec<String>,
+        depth: usize,
+    ) -> anyhow::Result<()> {
+        let Some(called) = self.macros.get(&tokens[0]) else {
+            self.instructions.push((line, tokens));
+            return Ok(());
+        };
+
+        if depth == MAX_MACRO_DEPTH {
+            return Err(anyhow!("Macros nested too deep in {}", tokens[0]));
+        }
+        let arguments = &tokens[1..];
+        if arguments.len() != called.parameters.len() {
+            return Err(anyhow!(
+                "Macro {} takes {} arguments, {} given",
+                tokens[0],
+                called.parameters.len(),
+                arguments.len()
+            ));
+        }
+
+        let expanded = called
+            .body
+            .iter()
+            .map(|body| {
+                // The mnemonic is kept, parameters only stand for operands
+                body.iter()
+                    .enumerate()
+                    .map(|(position, token)| {
+                        match called.parameters.iter().position(|p| p == token) {
+                            Some(index) if position > 0 => arguments[index].clone(),
+                            _ => token.clone(),
+                        }
+                    })
+                    .collect::<Vec<String>>()
+            })
+            .collect::<Vec<_>>();
+        for tokens in expanded {
+            self.statement(line, tokens, depth + 1)?;
+        }
+
+        Ok(())
+    }
+
+    fn resolve(&self, index: usize, tokens: &[String]) -> anyhow::Result<Op> {
+        let offset_position = match tokens[0].as_str() {
+            "jmp" => Some(1),
+            "jnz" => Some(2),
+            _ => None,
+        };
+        let resolved = tokens
+            .iter()
+            .enumerate()
+            .map(|(position, token)| {
+                if position == 0 || token.parse::<Register>().is_ok() {
+                    return Ok(token.clone());
+                }
+                if let Some(value) = self.constants.get(token) {
+                    return Ok(value.to_string());
+                }
+                match (Some(position) == offset_position, self.labels.get(token)) {
+                    (true, Some(&target)) => Ok((target as isize - index as isize).to_string()),
+                    (true, None) => Err(anyhow!("Unknown label: {}", token)),
+                    (false, _) => Ok(token.clone()),
+                }
+            })
+            .collect::<anyhow::Result<Vec<String>>>()?;
+
+        resolved.join(" ").parse::<Op>()
+    }
+}
+
+// Source lines hold one instruction each, with `;` starting a comment:
+//   .const NAME value      a value usable as an operand or an offset
+//   .macro name p1 p2 ...  instructions up to `.endm`, where parameters are replaced by the
+//                          arguments of each call `name a1 a2 ...`
+//   name:                  a label, usable as a jump target instead of an offset
+// Constants and macros must be defined before use, labels can be used anywhere.
+pub(super) fn assemble(source: &str) -> anyhow::Result<Vec<Op>> {
+    let mut assembler = Assembler::default();
+    let mut recording: Option<(LineNumber, String, Macro)> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let number = index + 1;
+        let code = line.split(';').next().unwrap_or_default();
+        let mut tokens = code
+            .split_whitespace()
+            .map(str::to_string)
+            .collect::<Vec<String>>();
+        let error = |e: anyhow::Error| anyhow!("Line {}: {}", number, e);
+
+        if let Some((start, name, mut body)) = recording.take() {
+            match tokens.first().map(String::as_str) {
+                Some(".endm") => {
+                    assembler.macros.insert(name, body);
+                }
+                Some(token) if token.ends_with(':') => {
+                    return Err(error(anyhow!("Labels are not allowed in macros")));
+                }
+                Some(token) if token.starts_with('.') => {
+                    return Err(error(anyhow!("Directives are not allowed in macros")));
+                }
+                Some(_) => {
+                    body.body.push(tokens);
+                    recording = Some((start, name, body));
+                }
+                None => recording = Some((start, name, body)),
+            }
+            continue;
+        }
+
+        if let Some(label) = tokens.first().and_then(|token| token.strip_suffix(':')) {
+            assembler.define_label(label).map_err(error)?;
+            tokens.remove(0);
+        }
+
+        let parts = tokens.iter().map(String::as_str).collect::<Vec<&str>>();
+        match parts[..] {
+            [] => {}
+            [".const", name, value] => assembler.define_constant(name, value).map_err(error)?,
+            [".macro", name, ref parameters @ ..] => {
+                check_symbol(name).map_err(error)?;
+                recording = Some((
+                    number,
+                    name.to_string(),
+                    Macro {
+                        parameters: parameters.iter().map(|p| p.to_string()).collect(),
+                        body: vec![],
+                    },
+                ));
+            }
+            [directive, ..] if directive.starts_with('.') => {
+                return Err(error(anyhow!("Invalid directive: {:?}", code.trim())));
+            }
+            _ => {
+                let tokens = parts.iter().map(|token| token.to_string()).collect();
+                assembler.statement(number, tokens, 0).map_err(error)?
+            }
+        }
+    }
+
+    if let Some((start, name, _)) = recording {
+        return Err(anyhow!(
+            "Line {}: macro {} is missing its .endm",
+            start,
+            name
+        ));
+    }
+
+    assembler
+        .instructions
+        .iter()
+        .enumerate()
+        .map(|(index, (number, tokens))| {
+            assembler
+                .resolve(index, tokens)
+                .map_err(|e| anyhow!("Line {}: {}", number, e))
+        })
+        .collect()
+}
+
+// One instruction per line, jumps landing inside the program go to generated labels
+pub(super) fn disassemble(program: &[Op]) -> String {
+    let target = |index: usize, offset: isize| {
+        index
+            .checked_add_signed(offset)
+            .filter(|&target| target <= program.len())
+    };
+    let labels = program
+        .iter()
+        .enumerate()
+        .filter_map(|(index, op)| match *op {
+            Op::Jmp(offset) | Op::Jnz(_, offset) => target(index, offset),
+            _ => None,
+        })
+        .collect::<std::collections::BTreeSet<usize>>()
+        .into_iter()
+        .enumerate()
+        .map(|(number, target)| (target, format!("l{}", number)))
+        .collect::<BTreeMap<usize, String>>();
+
+    let mut output = String::new();
+    for (index, op) in program.iter().enumerate() {
+        if let Some(label) = labels.get(&index) {
+            output.push_str(&format!("{}:\n", label));
+        }
+        let label = |offset| target(index, offset).and_then(|target| labels.get(&target));
+        let line = match *op {
+            Op::Jmp(offset) => match label(offset) {
+                Some(label) => format!("jmp {}", label),
+                None => op.to_string(),
+            },
+            Op::Jnz(operand, offset) => match label(offset) {
+                Some(label) => format!("jnz {} {}", operand, label),
+                None => op.to_string(),
+            },
+            _ => op.to_string(),
+        };
+        output.push_str(&format!("    {}\n", line));
+    }
+    if let Some(label) = labels.get(&program.len()) {
+        output.push_str(&format!("{}:\n", label));
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::instruction::Operand;
+    use super::*;
+
+    fn real_input() -> String {
+        std::fs::read_to_string("data/day10.txt").unwrap()
+    }
+
+    #[test]
+    fn should_assemble_puzzle_input_as_is() {
+        let source = real_input();
+        let expected = source
+            .lines()
+            .map(|line| line.parse::<Op>().unwrap())
+            .collect::<Vec<Op>>();
+        assert_eq!(assemble(&source).unwrap(), expected);
+    }
+
+    #[test]
+    fn disassembled_puzzle_input_should_assemble_back() {
+        let program = assemble(&real_input()).unwrap();
+        assert_eq!(assemble(&disassemble(&program)).unwrap(), program);
+    }
+
+    #[test]
+    fn should_resolve_labels_constants_and_macros() {
+        let source = "
+            ; x = 1 + 2 + ... + COUNT
+            .const COUNT 5
+            .const START COUNT
+            .macro decrement reg
+                sub reg 1       ; one less
+            .endm
+            .macro countdown reg target
+                decrement reg
+                jnz reg target
+            .endm
+
+                set a START
+                setx 0
+            loop:
+                add x a
+                countdown a loop
+            end: noop
+                jmp end
+        ";
+        let program = assemble(source).unwrap();
+        assert_eq!(
+            program,
+            vec![
+                Op::Set(Reg::A, Operand::Value(5)),
+                Op::Set(Reg::X, Operand::Value(0)),
+                Op::Add(Reg::X, Operand::Reg(Reg::A)),
+                Op::Sub(Reg::A, Operand::Value(1)),
+                Op::Jnz(Operand::Reg(Reg::A), -2),
+                Op::Noop,
+                Op::Jmp(-1),
+            ]
+        );
+        assert_eq!(
+            disassemble(&program),
+            "    set a 5\n    setx 0\nl0:\n    addx a\n    sub a 1\n    jnz a l0\nl1:\n    noop\n    jmp l1\n"
+        );
+    }
+
+    #[test]
+    fn should_report_errors_with_line_numbers() {
+        let error = |source: &str| assemble(source).unwrap_err().to_string();
+        assert_eq!(error("noop\njmp nowhere"), "Line 2: Unknown label: nowhere");
+        assert_eq!(
+            error("here: noop\nhere: noop"),
+            "Line 2: Label here defined twice"
+        );
+        assert_eq!(error(".const x 1"), "Line 1: Invalid name: \"x\"");
+        assert_eq!(error(".const N y"), "Line 1: Invalid constant value: \"y\"");
+        assert_eq!(
+            error(".macro m\nl: noop\n.endm"),
+            "Line 2: Labels are not allowed in macros"
+        );
+        assert_eq!(
+            error("noop\n.macro m\nnoop"),
+            "Line 2: macro m is missing its .endm"
+        );
+        assert_eq!(
+            error(".macro m\n.const N 1\n.endm"),
+            "Line 2: Directives are not allowed in macros"
+        );
+        assert_eq!(
+            error(".macro m op\nop x 1\n.endm\nm add"),
+            "Line 4: Invalid instruction: \"op x 1\""
+        );
+        assert_eq!(
+            error(".macro m a\nnoop\n.endm\nm"),
+            "Line 4: Macro m takes 1 arguments, 0 given"
+        );
+        assert_eq!(
+            error(".macro m\nm\n.endm\nm"),
+            "Line 4: Macros nested too deep in m"
+        );
+        assert_eq!(
+            error("noop\n\n.org 3"),
+            "Line 3: Invalid directive: \".org 3\""
+        );
+        assert_eq!(error("addx"), "Line 1: Invalid instruction: \"addx\"");
+    }
+
+    #[test]
+    fn should_keep_jumps_leaving_the_program_as_offsets() {
+        let program = vec![Op::Jmp(-1), Op::Jnz(Operand::Value(1), 5), Op::Jmp(1)];
+        assert_eq!(
+            disassemble(&program),
+            "    jmp -1\n    jnz 1 5\n    jmp l0\nl0:\n"
+        );
+        assert_eq!(assemble(&disassemble(&program)).unwrap(), program);
+    }
+}
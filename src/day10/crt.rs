@@ -0,0 +1,133 @@
+use std::fmt;
+
+use crate::grid::Grid;
+
+use super::{Cycle, Trace};
+
+pub(super) const WIDTH: usize = 40;
+pub(super) const HEIGHT: usize = 6;
+
+// Capital letters drawn by the puzzle, 4 pixels wide and 6 high
+const GLYPHS: [(char, [&str; HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+// Letters are 4 pixels wide with a blank column after each
+const GLYPH_WIDTH: usize = 5;
+
+pub(super) struct Crt {
+    pixels: Grid<bool>,
+}
+
+impl Crt {
+    // The beam draws one pixel per cycle, row after row, and the pixel is lit when the
+    // 3 pixels wide sprite centered on the register covers it
+    pub(super) fn draw(trace: &Trace) -> Self {
+        Self::draw_until(trace, WIDTH * HEIGHT)
+    }
+
+    // Pixels of the cycles after `last` stay dark
+    pub(super) fn draw_until(trace: &Trace, last: Cycle) -> Self {
+        let mut pixels = Grid::filled(WIDTH, HEIGHT, false);
+        for position in pixels.positions().take(last).collect::<Vec<_>>() {
+            let (row, col) = position;
+            let cycle: Cycle = row * WIDTH + col + 1;
+            let sprite = trace.register_during(cycle);
+            pixels[position] = (col as i32).abs_diff(sprite) <= 1;
+        }
+
+        Self { pixels }
+    }
+
+    fn glyph(&self, index: usize) -> String {
+        (0..HEIGHT)
+            .flat_map(|row| (0..4).map(move |col| (row, index * GLYPH_WIDTH + col)))
+            .map(|position| match self.pixels.get(position) {
+                Some(true) => '#',
+                _ => '.',
+            })
+            .collect()
+    }
+
+    // Unknown glyphs are decoded as '?'
+    pub(super) fn decode(&self) -> String {
+        (0..WIDTH.div_ceil(GLYPH_WIDTH))
+            .map(|index| {
+                let glyph = self.glyph(index);
+                GLYPHS
+                    .iter()
+                    .find(|(_, rows)| rows.concat() == glyph)
+                    .map_or('?', |&(letter, _)| letter)
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Crt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in 0..HEIGHT {
+            for &lit in self.pixels.row(row) {
+                write!(f, "{}", if lit { '#' } else { '.' })?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::tests::program;
+    use super::*;
+
+    fn crt(rows: &[&str]) -> Crt {
+        let mut pixels = Grid::filled(WIDTH, HEIGHT, false);
+        for (row, line) in rows.iter().enumerate() {
+            for (col, pixel) in line.chars().enumerate() {
+                pixels[(row, col)] = pixel == '#';
+            }
+        }
+
+        Crt { pixels }
+    }
+
+    #[test]
+    fn should_follow_sprite_position() {
+        let program = program(&["noop", "addx 3", "addx -5"]);
+        let crt = Crt::draw(&Trace::run_program(&program));
+        let first_row = crt.to_string().lines().next().unwrap().to_string();
+        assert_eq!(first_row, format!("#####{}", ".".repeat(WIDTH - 5)));
+        assert_eq!(crt.to_string().lines().count(), HEIGHT);
+    }
+
+    #[test]
+    fn should_decode_letters() {
+        let crt = crt(&[
+            ".##..###..####.#..#.###..#..#.",
+            "#..#.#..#.#....#..#.#..#.#..#.",
+            "#..#.###..###..####.#..#.#..#.",
+            "####.#..#.#....#..#.###..#..#.",
+            "#..#.#..#.#....#..#.#....#..#.",
+            "#..#.###..####.#..#.#.....##..",
+        ]);
+        assert_eq!(crt.decode(), "ABEHPU??");
+    }
+}
@@ -0,0 +1,486 @@
+use anyhow::anyhow;
+use std::{
+    fmt,
+    io::{BufRead, Write},
+    str::FromStr,
+};
+
+use super::crt::Crt;
+use super::instruction::{Op, Reg};
+use super::{Cpu, Cycle, Register, Registers, Tick, Trace};
+
+// `continue` pauses after this many cycles without a breakpoint, programs may loop forever
+const MAX_CONTINUE: Cycle = 1_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    const ALL: [(Comparison, &'static str); 6] = [
+        (Comparison::Less, "<"),
+        (Comparison::LessOrEqual, "<="),
+        (Comparison::Greater, ">"),
+        (Comparison::GreaterOrEqual, ">="),
+        (Comparison::Equal, "=="),
+        (Comparison::NotEqual, "!="),
+    ];
+
+    fn holds(&self, left: Register, right: Register) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        Comparison::ALL
+            .iter()
+            .find(|(comparison, _)| comparison == self)
+            .map_or("?", |&(_, symbol)| symbol)
+    }
+}
+
+impl FromStr for Comparison {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Comparison::ALL
+            .iter()
+            .find(|&&(_, symbol)| symbol == s)
+            .map(|&(comparison, _)| comparison)
+            .ok_or_else(|| anyhow!("Unknown comparison: {:?}", s))
+    }
+}
+
+// Execution pauses before the cycle, before the instruction starts, or when the condition
+// becomes true
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Breakpoint {
+    Cycle(Cycle),
+    Instruction(usize),
+    Condition(Reg, Comparison, Register),
+}
+
+impl Breakpoint {
+    // `before` holds the registers before the last cycle, if any ran since resuming
+    fn hit(&self, cpu: &Cpu, before: Option<&Registers>) -> bool {
+        match *self {
+            Breakpoint::Cycle(cycle) => cpu.cycle + 1 == cycle && cpu.pointer < cpu.program.len(),
+            Breakpoint::Instruction(index) => cpu.pointer == index && cpu.elapsed == 0,
+            Breakpoint::Condition(reg, comparison, value) => before.is_some_and(|before| {
+                comparison.holds(cpu.registers[reg], value) && !comparison.holds(before[reg], value)
+            }),
+        }
+    }
+}
+
+// `cycle 20`, `at 5` or `x > 20`, register names are case insensitive
+impl FromStr for Breakpoint {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_whitespace().collect::<Vec<&str>>()[..] {
+            // Cycles start at 1
+            ["cycle", "0"] => Err(anyhow!("Cycle 0 is never reached")),
+            ["cycle", cycle] => Ok(Breakpoint::Cycle(cycle.parse()?)),
+            ["at", index] => Ok(Breakpoint::Instruction(index.parse()?)),
+            [reg, comparison, value] => Ok(Breakpoint::Condition(
+                reg.to_lowercase().parse()?,
+                comparison.parse()?,
+                value.parse()?,
+            )),
+            _ => Err(anyhow!("Invalid breakpoint: {:?}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Breakpoint::Cycle(cycle) => write!(f, "cycle {}", cycle),
+            Breakpoint::Instruction(index) => write!(f, "at {}", index),
+            Breakpoint::Condition(reg, comparison, value) => {
+                write!(f, "{} {} {}", reg.name(), comparison.symbol(), value)
+            }
+        }
+    }
+}
+
+struct Debugger<'a> {
+    cpu: Cpu<'a>,
+    ticks: Vec<Tick>,
+    breakpoints: Vec<Breakpoint>,
+    // Whether execution stopped on a breakpoint and has not moved since
+    paused: bool,
+}
+
+impl<'a> Debugger<'a> {
+    fn new(program: &'a [Op]) -> Self {
+        Self {
+            cpu: Cpu::new(program),
+            ticks: vec![],
+            breakpoints: vec![],
+            paused: false,
+        }
+    }
+
+    fn tick(&mut self) -> bool {
+        match self.cpu.tick() {
+            Some(tick) => {
+                self.ticks.push(tick);
+                self.paused = false;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Steps at most MAX_CONTINUE cycles, returns false when more were asked for
+    fn step(&mut self, cycles: usize) -> bool {
+        for _ in 0..cycles.min(MAX_CONTINUE) {
+            if !self.tick() {
+                return true;
+            }
+        }
+
+        cycles <= MAX_CONTINUE
+    }
+
+    // Runs until the current instruction is over
+    fn next(&mut self) {
+        while self.tick() && self.cpu.elapsed != 0 {}
+    }
+
+    // Runs until a breakpoint is hit before a cycle, the breakpoints execution is paused on
+    // are skipped so that it moves on
+    fn resume(&mut self) -> Option<Breakpoint> {
+        let mut before = None;
+        for _ in 0..MAX_CONTINUE {
+            if !self.paused {
+                let hit = self
+                    .breakpoints
+                    .iter()
+                    .find(|b| b.hit(&self.cpu, before.as_ref()));
+                if let Some(&breakpoint) = hit {
+                    self.paused = true;
+                    return Some(breakpoint);
+                }
+            }
+            let registers = self.cpu.registers;
+            if !self.tick() {
+                return None;
+            }
+            before = Some(registers);
+        }
+
+        None
+    }
+
+    fn halted(&self) -> bool {
+        self.cpu.pointer >= self.cpu.program.len()
+    }
+
+    fn status(&self) -> String {
+        let next = match self.cpu.program.get(self.cpu.pointer) {
+            Some(op) => format!(
+                "next #{} {} ({}/{} cycles)",
+                self.cpu.pointer,
+                op,
+                self.cpu.elapsed,
+                op.cycles()
+            ),
+            None => "halted".to_string(),
+        };
+        format!(
+            "cycle {}, x = {}, {}",
+            self.cpu.cycle,
+            self.cpu.registers[Reg::X],
+            next
+        )
+    }
+
+    fn registers(&self) -> String {
+        Reg::ALL
+            .iter()
+            .map(|&reg| format!("{} = {}", reg.name(), self.cpu.registers[reg]))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    fn crt(&self) -> Crt {
+        let trace = Trace {
+            ticks: self.ticks.clone(),
+        };
+        Crt::draw_until(&trace, self.cpu.cycle)
+    }
+
+    // A few instructions around the pointer
+    fn list(&self) -> String {
+        let first = self.cpu.pointer.saturating_sub(3);
+        self.cpu
+            .program
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(7)
+            .map(|(index, op)| {
+                let marker = if index == self.cpu.pointer {
+                    "=>"
+                } else {
+                    "  "
+                };
+                format!("{} {:>4} {}\n", marker, index, op)
+            })
+            .collect()
+    }
+}
+
+fn run_command<W: Write>(
+    debugger: &mut Debugger,
+    line: &str,
+    output: &mut W,
+) -> anyhow::Result<()> {
+    let (command, argument) = match line.trim().split_once(' ') {
+        Some((command, argument)) => (command, argument.trim()),
+        None => (line.trim(), ""),
+    };
+
+    match (command, argument) {
+        ("", _) => return Ok(()),
+        ("step" | "s", cycles) => {
+            let cycles = match cycles {
+                "" => 1,
+                cycles => cycles.parse()?,
+            };
+            if !debugger.step(cycles) {
+                writeln!(output, "paused after {} cycles", MAX_CONTINUE)?;
+            }
+        }
+        ("next" | "n", "") => debugger.next(),
+        ("continue" | "c", "") => match debugger.resume() {
+            Some(breakpoint) => writeln!(output, "breakpoint {}", breakpoint)?,
+            None if debugger.halted() => {}
+            None => writeln!(output, "paused after {} cycles", MAX_CONTINUE)?,
+        },
+        ("break" | "b", breakpoint) => {
+            let breakpoint = breakpoint.parse::<Breakpoint>()?;
+            debugger.breakpoints.push(breakpoint);
+            writeln!(
+                output,
+                "breakpoint {}: {}",
+                debugger.breakpoints.len(),
+                breakpoint
+            )?;
+            return Ok(());
+        }
+        ("delete" | "d", number) => {
+            let number = number.parse::<usize>()?;
+            if number == 0 || number > debugger.breakpoints.len() {
+                return Err(anyhow!("No breakpoint {}", number));
+            }
+            debugger.breakpoints.remove(number - 1);
+            return Ok(());
+        }
+        ("breakpoints", "") => {
+            for (index, breakpoint) in debugger.breakpoints.iter().enumerate() {
+                writeln!(output, "{}: {}", index + 1, breakpoint)?;
+            }
+            return Ok(());
+        }
+        ("registers" | "r", "") => {
+            writeln!(output, "{}", debugger.registers())?;
+            return Ok(());
+        }
+        ("crt", "") => {
+            write!(output, "{}", debugger.crt())?;
+            return Ok(());
+        }
+        ("list" | "l", "") => {
+            write!(output, "{}", debugger.list())?;
+            return Ok(());
+        }
+        _ => {
+            writeln!(
+                output,
+                "commands: step [cycles], next, continue, break <cycle N|at N|REG OP N>, delete <n>, breakpoints, registers, crt, list, quit"
+            )?;
+            return Ok(());
+        }
+    }
+
+    writeln!(output, "{}", debugger.status())?;
+    Ok(())
+}
+
+pub(super) fn debug<R: BufRead, W: Write>(
+    program: &[Op],
+    input: R,
+    output: &mut W,
+) -> anyhow::Result<()> {
+    let mut debugger = Debugger::new(program);
+    writeln!(output, "{}", debugger.status())?;
+    write!(output, "(debug) ")?;
+    output.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        if matches!(line.trim(), "quit" | "q" | "exit") {
+            break;
+        }
+
+        if let Err(e) = run_command(&mut debugger, &line, output) {
+            writeln!(output, "error: {}", e)?;
+        }
+        write!(output, "(debug) ")?;
+        output.flush()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::tests::program;
+    use super::*;
+
+    fn session(lines: &[&str], commands: &str) -> String {
+        let program = program(lines);
+        let mut output = vec![];
+        debug(&program, commands.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn countdown() -> Vec<Op> {
+        program(&["set a 3", "addx 10", "sub a 1", "jnz a -2", "noop"])
+    }
+
+    #[test]
+    fn should_parse_breakpoints() {
+        assert_eq!(
+            "cycle 20".parse::<Breakpoint>().unwrap(),
+            Breakpoint::Cycle(20)
+        );
+        assert_eq!(
+            "at 3".parse::<Breakpoint>().unwrap(),
+            Breakpoint::Instruction(3)
+        );
+        assert_eq!(
+            "X > 20".parse::<Breakpoint>().unwrap(),
+            Breakpoint::Condition(Reg::X, Comparison::Greater, 20)
+        );
+        assert_eq!(
+            "a != -1".parse::<Breakpoint>().unwrap().to_string(),
+            "a != -1"
+        );
+        assert!("x => 1".parse::<Breakpoint>().is_err());
+        assert!("cycle".parse::<Breakpoint>().is_err());
+    }
+
+    #[test]
+    fn should_step_cycles_and_instructions() {
+        let program = countdown();
+        let mut debugger = Debugger::new(&program);
+        debugger.step(2);
+        assert_eq!(
+            debugger.status(),
+            "cycle 2, x = 1, next #1 addx 10 (1/2 cycles)"
+        );
+        debugger.next();
+        assert_eq!(
+            debugger.status(),
+            "cycle 3, x = 11, next #2 sub a 1 (0/2 cycles)"
+        );
+        debugger.step(100);
+        assert!(debugger.halted());
+        assert_eq!(debugger.cpu.registers[Reg::X], 31);
+    }
+
+    #[test]
+    fn should_stop_on_breakpoints() {
+        let program = countdown();
+        let mut debugger = Debugger::new(&program);
+        debugger.breakpoints = vec![Breakpoint::Cycle(5)];
+        assert_eq!(debugger.resume(), Some(Breakpoint::Cycle(5)));
+        assert_eq!(debugger.cpu.cycle, 4);
+
+        debugger.breakpoints = vec![Breakpoint::Instruction(1)];
+        assert_eq!(debugger.resume(), Some(Breakpoint::Instruction(1)));
+        assert_eq!((debugger.cpu.cycle, debugger.cpu.registers[Reg::A]), (7, 2));
+
+        debugger.breakpoints = vec!["x >= 30".parse().unwrap()];
+        assert!(debugger.resume().is_some());
+        assert_eq!(debugger.cpu.registers[Reg::X], 31);
+
+        assert_eq!(debugger.resume(), None);
+        assert!(debugger.halted());
+    }
+
+    #[test]
+    fn should_stop_before_the_first_cycle() {
+        let program = countdown();
+        for breakpoint in [Breakpoint::Cycle(1), Breakpoint::Instruction(0)] {
+            let mut debugger = Debugger::new(&program);
+            debugger.breakpoints = vec![breakpoint];
+            assert_eq!(debugger.resume(), Some(breakpoint));
+            assert_eq!(debugger.cpu.cycle, 0);
+            // Resuming again moves past the breakpoint
+            assert_eq!(debugger.resume(), None);
+            assert!(debugger.halted());
+        }
+
+        let mut debugger = Debugger::new(&program);
+        debugger.breakpoints = vec![Breakpoint::Instruction(1)];
+        debugger.step(1);
+        assert_eq!(debugger.resume(), Some(Breakpoint::Instruction(1)));
+        assert_eq!(debugger.cpu.cycle, 1);
+        assert_eq!(debugger.resume(), Some(Breakpoint::Instruction(1)));
+        assert_eq!(debugger.cpu.cycle, 7);
+        assert!("cycle 0".parse::<Breakpoint>().is_err());
+    }
+
+    #[test]
+    fn should_not_hang_on_infinite_loops() {
+        let output = session(&["jmp 0"], "c\n");
+        assert!(output.contains(&format!("paused after {} cycles", MAX_CONTINUE)));
+        let output = session(&["jmp 0"], &format!("step {}\n", usize::MAX));
+        assert!(output.contains(&format!("paused after {} cycles", MAX_CONTINUE)));
+        assert!(!session(&["jmp 0"], "step 5\n").contains("paused"));
+        assert!(!session(&["noop"], &format!("step {}\n", usize::MAX)).contains("paused"));
+    }
+
+    #[test]
+    fn should_run_a_session() {
+        let output = session(
+            &["noop", "addx 3", "addx -5"],
+            "break x < 0\nbreakpoints\nc\nregisters\ncrt\nlist\ndelete 2\nfoo\nq\nstep\n",
+        );
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert_eq!(lines[0], "cycle 0, x = 1, next #0 noop (0/1 cycles)");
+        assert_eq!(lines[1], "(debug) breakpoint 1: x < 0");
+        assert_eq!(lines[2], "(debug) 1: x < 0");
+        assert_eq!(lines[3], "(debug) breakpoint x < 0");
+        assert_eq!(lines[4], "cycle 5, x = -1, halted");
+        assert_eq!(
+            lines[5],
+            "(debug) x = -1, a = 0, b = 0, c = 0, d = 0, f = 0"
+        );
+        assert_eq!(lines[6], format!("(debug) #####{}", ".".repeat(35)));
+        assert_eq!(lines[7], ".".repeat(40));
+        assert_eq!(lines[12], "(debug)       0 noop");
+        assert_eq!(lines[15], "(debug) error: No breakpoint 2");
+        assert!(lines[16].starts_with("(debug) commands:"));
+        assert_eq!(lines[17], "(debug) ");
+        assert_eq!(lines.len(), 18);
+    }
+}
@@ -0,0 +1,225 @@
+use anyhow::anyhow;
+
+use super::instruction::{Op, Operand, Reg};
+
+// Starts with a NUL byte, which no assembly source starts with
+const MAGIC: &[u8] = b"\0D10";
+
+// Each instruction starts with a byte holding the opcode in its high nibble, the register
+// index in the next 3 bits and whether the operand is a register in the lowest one. Register
+// operands follow as their index, values and offsets as zigzag LEB128 varints.
+const NOOP: u8 = 0;
+const ADD: u8 = 1;
+const SUB: u8 = 2;
+const MUL: u8 = 3;
+const SET: u8 = 4;
+const CMP: u8 = 5;
+const JMP: u8 = 6;
+const JNZ: u8 = 7;
+
+fn write_varint(value: i64, output: &mut Vec<u8>) {
+    let mut value = ((value << 1) ^ (value >> 63)) as u64;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            output.push(byte);
+            return;
+        }
+        output.push(byte | 0x80);
+    }
+}
+
+fn write_operand(operand: Operand, output: &mut Vec<u8>) {
+    match operand {
+        Operand::Value(value) => write_varint(value as i64, output),
+        Operand::Reg(reg) => output.push(reg as u8),
+    }
+}
+
+fn header(opcode: u8, reg: Reg, operand: Option<Operand>) -> u8 {
+    let is_reg = matches!(operand, Some(Operand::Reg(_))) as u8;
+    opcode << 4 | (reg as u8) << 1 | is_reg
+}
+
+pub(super) fn encode(program: &[Op]) -> Vec<u8> {
+    let mut output = MAGIC.to_vec();
+    for op in program {
+        match *op {
+            Op::Noop => output.push(header(NOOP, Reg::X, None)),
+            Op::Add(reg, operand)
+            | Op::Sub(reg, operand)
+            | Op::Mul(reg, operand)
+            | Op::Set(reg, operand)
+            | Op::Cmp(reg, operand) => {
+                let opcode = match op {
+                    Op::Add(..) => ADD,
+                    Op::Sub(..) => SUB,
+                    Op::Mul(..) => MUL,
+                    Op::Set(..) => SET,
+                    _ => CMP,
+                };
+                output.push(header(opcode, reg, Some(operand)));
+                write_operand(operand, &mut output);
+            }
+            Op::Jmp(offset) => {
+                output.push(header(JMP, Reg::X, None));
+                write_varint(offset as i64, &mut output);
+            }
+            Op::Jnz(operand, offset) => {
+                output.push(header(JNZ, Reg::X, Some(operand)));
+                write_operand(operand, &mut output);
+                write_varint(offset as i64, &mut output);
+            }
+        }
+    }
+
+    output
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> anyhow::Result<u8> {
+        let byte = *self
+            .bytes
+            .get(self.position)
+            .ok_or_else(|| anyhow!("Unexpected end of program at byte {}", self.position))?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn varint(&mut self) -> anyhow::Result<i64> {
+        let start = self.position;
+        let mut value: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok((value >> 1) as i64 ^ -((value & 1) as i64));
+            }
+        }
+
+        Err(anyhow!("Invalid number at byte {}", start))
+    }
+
+    fn reg(&mut self, index: u8) -> anyhow::Result<Reg> {
+        Reg::ALL
+            .get(index as usize)
+            .copied()
+            .ok_or_else(|| anyhow!("Invalid register {} at byte {}", index, self.position))
+    }
+
+    fn operand(&mut self, is_reg: bool) -> anyhow::Result<Operand> {
+        match is_reg {
+            true => {
+                let index = self.byte()?;
+                Ok(Operand::Reg(self.reg(index)?))
+            }
+            false => {
+                let value = self.varint()?;
+                let value = value.try_into().map_err(|_| {
+                    anyhow!("Value {} out of range at byte {}", value, self.position)
+                })?;
+                Ok(Operand::Value(value))
+            }
+        }
+    }
+
+    fn offset(&mut self) -> anyhow::Result<isize> {
+        let offset = self.varint()?;
+        offset
+            .try_into()
+            .map_err(|_| anyhow!("Offset {} out of range at byte {}", offset, self.position))
+    }
+}
+
+pub(super) fn is_encoded(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+pub(super) fn decode(bytes: &[u8]) -> anyhow::Result<Vec<Op>> {
+    let Some(bytes) = bytes.strip_prefix(MAGIC) else {
+        return Err(anyhow!("Not a day10 program"));
+    };
+
+    let mut reader = Reader { bytes, position: 0 };
+    let mut program = vec![];
+    while reader.position < bytes.len() {
+        let header = reader.byte()?;
+        let reg = reader.reg((header >> 1) & 0b111)?;
+        let is_reg = header & 1 == 1;
+        let op = match header >> 4 {
+            NOOP => Op::Noop,
+            ADD => Op::Add(reg, reader.operand(is_reg)?),
+            SUB => Op::Sub(reg, reader.operand(is_reg)?),
+            MUL => Op::Mul(reg, reader.operand(is_reg)?),
+            SET => Op::Set(reg, reader.operand(is_reg)?),
+            CMP => Op::Cmp(reg, reader.operand(is_reg)?),
+            JMP => Op::Jmp(reader.offset()?),
+            JNZ => Op::Jnz(reader.operand(is_reg)?, reader.offset()?),
+            opcode => {
+                return Err(anyhow!(
+                    "Invalid opcode {} at byte {}",
+                    opcode,
+                    reader.position - 1
+                ))
+            }
+        };
+        program.push(op);
+    }
+
+    Ok(program)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::assembler::{assemble, disassemble};
+    use super::*;
+
+    #[test]
+    fn puzzle_input_should_survive_encoding() {
+        let source = std::fs::read_to_string("data/day10.txt").unwrap();
+        let program = assemble(&source).unwrap();
+        let bytes = encode(&program);
+        assert!(bytes.len() < source.len() / 2);
+        assert_eq!(decode(&bytes).unwrap(), program);
+        assert_eq!(
+            assemble(&disassemble(&decode(&bytes).unwrap())).unwrap(),
+            program
+        );
+    }
+
+    #[test]
+    fn should_encode_every_instruction() {
+        let program = assemble(
+            "noop\naddx -1\nsub a x\nmul b 1000000\nset f -2147483648\ncmp d c\njmp -300\njnz f 2\njnz 0 -1",
+        )
+        .unwrap();
+        assert_eq!(decode(&encode(&program)).unwrap(), program);
+        assert_eq!(encode(&[Op::Noop]), b"\0D10\x00");
+        assert_eq!(
+            encode(&[Op::Add(Reg::X, Operand::Value(-1))]),
+            b"\0D10\x10\x01"
+        );
+        assert_eq!(
+            encode(&[Op::Sub(Reg::A, Operand::Reg(Reg::X))]),
+            b"\0D10\x23\x00"
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_programs() {
+        let error = |bytes: &[u8]| decode(bytes).unwrap_err().to_string();
+        assert_eq!(error(b"noop"), "Not a day10 program");
+        assert_eq!(error(b"D10: noop"), "Not a day10 program");
+        assert_eq!(error(b"\0D10\x10"), "Unexpected end of program at byte 1");
+        assert_eq!(error(b"\0D10\x90"), "Invalid opcode 9 at byte 0");
+        assert_eq!(error(b"\0D10\x11\x09"), "Invalid register 9 at byte 2");
+        assert!(decode(b"\0D10").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,164 @@
+use anyhow::anyhow;
+use std::{fmt, str::FromStr};
+
+use super::Register;
+
+// `x` is the register the CRT and the signal strength read, `cmp` stores its result in `f`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) enum Reg {
+    X,
+    A,
+    B,
+    C,
+    D,
+    F,
+}
+
+impl Reg {
+    pub(super) const ALL: [Reg; 6] = [Reg::X, Reg::A, Reg::B, Reg::C, Reg::D, Reg::F];
+
+    pub(super) fn name(&self) -> &'static str {
+        match self {
+            Reg::X => "x",
+            Reg::A => "a",
+            Reg::B => "b",
+            Reg::C => "c",
+            Reg::D => "d",
+            Reg::F => "f",
+        }
+    }
+}
+
+impl FromStr for Reg {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Reg::ALL
+            .into_iter()
+            .find(|reg| reg.name() == s)
+            .ok_or_else(|| anyhow!("Unknown register: {:?}", s))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Operand {
+    Value(Register),
+    Reg(Reg),
+}
+
+impl FromStr for Operand {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<Register>() {
+            Ok(value) => Ok(Operand::Value(value)),
+            Err(_) => Ok(Operand::Reg(s.parse::<Reg>()?)),
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Value(value) => write!(f, "{}", value),
+            Operand::Reg(reg) => write!(f, "{}", reg.name()),
+        }
+    }
+}
+
+// Jump offsets are relative to the jump itself
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Op {
+    Noop,
+    Add(Reg, Operand),
+    Sub(Reg, Operand),
+    Mul(Reg, Operand),
+    Set(Reg, Operand),
+    // Stores the sign of the difference, -1, 0 or 1, in `f`
+    Cmp(Reg, Operand),
+    Jmp(isize),
+    Jnz(Operand, isize),
+}
+
+impl Op {
+    pub(super) fn mnemonic(&self) -> &'static str {
+        match self {
+            Op::Noop => "noop",
+            Op::Add(..) => "add",
+            Op::Sub(..) => "sub",
+            Op::Mul(..) => "mul",
+            Op::Set(..) => "set",
+            Op::Cmp(..) => "cmp",
+            Op::Jmp(_) => "jmp",
+            Op::Jnz(..) => "jnz",
+        }
+    }
+
+    // Cycles each instruction takes, the shorthands working on `x` cost the same as their
+    // general form
+    pub(super) fn cycles(&self) -> usize {
+        match self {
+            Op::Noop => 1,
+            Op::Add(..) => 2,
+            Op::Sub(..) => 2,
+            Op::Mul(..) => 4,
+            Op::Set(..) => 1,
+            Op::Cmp(..) => 1,
+            Op::Jmp(_) => 1,
+            Op::Jnz(..) => 2,
+        }
+    }
+}
+
+fn arithmetic(mnemonic: &str, reg: Reg, operand: Operand) -> Option<Op> {
+    match mnemonic {
+        "add" => Some(Op::Add(reg, operand)),
+        "sub" => Some(Op::Sub(reg, operand)),
+        "mul" => Some(Op::Mul(reg, operand)),
+        "set" => Some(Op::Set(reg, operand)),
+        "cmp" => Some(Op::Cmp(reg, operand)),
+        _ => None,
+    }
+}
+
+impl FromStr for Op {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s.split_whitespace().collect::<Vec<&str>>();
+        let invalid = || anyhow!("Invalid instruction: {:?}", s);
+
+        match parts[..] {
+            ["noop"] => Ok(Op::Noop),
+            ["jmp", offset] => Ok(Op::Jmp(offset.parse()?)),
+            ["jnz", operand, offset] => Ok(Op::Jnz(operand.parse()?, offset.parse()?)),
+            // Shorthands working on `x`
+            [mnemonic @ ("addx" | "subx" | "mulx" | "setx"), operand] => {
+                arithmetic(&mnemonic[..3], Reg::X, operand.parse()?).ok_or_else(invalid)
+            }
+            [mnemonic, reg, operand] => {
+                arithmetic(mnemonic, reg.parse()?, operand.parse()?).ok_or_else(invalid)
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::Noop => write!(f, "noop"),
+            Op::Add(Reg::X, operand)
+            | Op::Sub(Reg::X, operand)
+            | Op::Mul(Reg::X, operand)
+            | Op::Set(Reg::X, operand) => write!(f, "{}x {}", self.mnemonic(), operand),
+            Op::Add(reg, operand)
+            | Op::Sub(reg, operand)
+            | Op::Mul(reg, operand)
+            | Op::Set(reg, operand)
+            | Op::Cmp(reg, operand) => write!(f, "{} {} {}", self.mnemonic(), reg.name(), operand),
+            Op::Jmp(offset) => write!(f, "jmp {}", offset),
+            Op::Jnz(operand, offset) => write!(f, "jnz {} {}", operand, offset),
+        }
+    }
+}
@@ -0,0 +1,371 @@
+use anyhow::anyhow;
+use std::ops::{Index, IndexMut};
+
+// (row, column), the origin is the top left corner
+pub type Position = (usize, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    pub const ORTHOGONAL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    pub const ALL: [Direction; 8] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+        Direction::UpLeft,
+        Direction::UpRight,
+        Direction::DownLeft,
+        Direction::DownRight,
+    ];
+
+    pub fn step(&self) -> (isize, isize) {
+        match self {
+            Direction::Up => (-1, 0),
+            Direction::Down => (1, 0),
+            Direction::Left => (0, -1),
+            Direction::Right => (0, 1),
+            Direction::UpLeft => (-1, -1),
+            Direction::UpRight => (-1, 1),
+            Direction::DownLeft => (1, -1),
+            Direction::DownRight => (1, 1),
+        }
+    }
+
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::UpLeft => Direction::DownRight,
+            Direction::UpRight => Direction::DownLeft,
+            Direction::DownLeft => Direction::UpRight,
+            Direction::DownRight => Direction::UpLeft,
+        }
+    }
+}
+
+// Cells are stored row after row
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> anyhow::Result<Self> {
+        if cells.len() != width * height {
+            return Err(anyhow!(
+                "Invalid grid: {} cells for {}x{}",
+                cells.len(),
+                width,
+                height
+            ));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    pub fn filled(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+
+    // One row per line, `parse` turns each character into a cell
+    pub fn parse(s: &str, parse: impl Fn(char) -> Option<T>) -> anyhow::Result<Self> {
+        let mut width = None;
+        let mut cells = vec![];
+        let mut height = 0;
+        for (index, line) in s.lines().enumerate() {
+            let row = line
+                .chars()
+                .map(|c| {
+                    parse(c).ok_or_else(|| anyhow!("Line {}: invalid cell {:?}", index + 1, c))
+                })
+                .collect::<anyhow::Result<Vec<T>>>()?;
+            match width {
+                Some(width) if width != row.len() => {
+                    return Err(anyhow!(
+                        "Line {}: {} cells, expected {}",
+                        index + 1,
+                        row.len(),
+                        width
+                    ));
+                }
+                _ => width = Some(row.len()),
+            }
+            cells.extend(row);
+            height += 1;
+        }
+
+        Self::new(width.unwrap_or(0), height, cells)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, (row, col): Position) -> Option<&T> {
+        match row < self.height && col < self.width {
+            true => Some(&self.cells[row * self.width + col]),
+            false => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, (row, col): Position) -> Option<&mut T> {
+        match row < self.height && col < self.width {
+            true => Some(&mut self.cells[row * self.width + col]),
+            false => None,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.cells.iter()
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Position> {
+        let width = self.width;
+        (0..self.height).flat_map(move |row| (0..width).map(move |col| (row, col)))
+    }
+
+    pub fn row(&self, row: usize) -> impl Iterator<Item = &T> + '_ {
+        self.cells[row * self.width..(row + 1) * self.width].iter()
+    }
+
+    // Only transposing reads columns for now, day08 walks rays instead
+    #[allow(dead_code)]
+    pub fn column(&self, col: usize) -> impl Iterator<Item = &T> + '_ {
+        (0..self.height).map(move |row| &self[(row, col)])
+    }
+
+    // Position one step away, if it is still inside the grid
+    pub fn offset(&self, (row, col): Position, (d_row, d_col): (isize, isize)) -> Option<Position> {
+        let row = row.checked_add_signed(d_row)?;
+        let col = col.checked_add_signed(d_col)?;
+        match row < self.height && col < self.width {
+            true => Some((row, col)),
+            false => None,
+        }
+    }
+
+    // Positions met walking from `from`, excluded, to the edge of the grid
+    pub fn ray(&self, from: Position, direction: Direction) -> impl Iterator<Item = Position> + '_ {
+        let step = direction.step();
+        std::iter::successors(self.offset(from, step), move |&position| {
+            self.offset(position, step)
+        })
+    }
+
+    // For the grid days to come, day08 sightlines go past the adjacent cells
+    #[allow(dead_code)]
+    pub fn neighbours(&self, position: Position) -> impl Iterator<Item = Position> + '_ {
+        Direction::ORTHOGONAL
+            .into_iter()
+            .filter_map(move |direction| self.offset(position, direction.step()))
+    }
+
+    // Diagonals included, unused until a day needs 8-way neighbours
+    #[allow(dead_code)]
+    pub fn neighbours8(&self, position: Position) -> impl Iterator<Item = Position> + '_ {
+        (-1..=1)
+            .flat_map(|d_row| (-1..=1).map(move |d_col| (d_row, d_col)))
+            .filter(|&step| step != (0, 0))
+            .filter_map(move |step| self.offset(position, step))
+    }
+
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    // day08 sweeps every direction in place, so nothing transposes a grid yet
+    #[allow(dead_code)]
+    pub fn transpose(&self) -> Self
+    where
+        T: Clone,
+    {
+        Grid {
+            width: self.height,
+            height: self.width,
+            cells: (0..self.width)
+                .flat_map(|col| self.column(col).cloned())
+                .collect(),
+        }
+    }
+
+    // Quarter turn clockwise, the first column becomes the first row read bottom up. Not
+    // used by day08, kept for puzzles that turn their maps.
+    #[allow(dead_code)]
+    pub fn rotate(&self) -> Self
+    where
+        T: Clone,
+    {
+        Grid {
+            width: self.height,
+            height: self.width,
+            cells: (0..self.width)
+                .flat_map(|col| {
+                    (0..self.height)
+                        .rev()
+                        .map(move |row| self[(row, col)].clone())
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Grid<u8> {
+    pub fn parse_digits(s: &str) -> anyhow::Result<Self> {
+        Self::parse(s, |c| c.to_digit(10).map(|digit| digit as u8))
+    }
+}
+
+impl Grid<char> {
+    // day08 is made of digits, character maps are left to later days
+    #[allow(dead_code)]
+    pub fn parse_chars(s: &str) -> anyhow::Result<Self> {
+        Self::parse(s, Some)
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: Position) -> &T {
+        self.get(position)
+            .unwrap_or_else(|| panic!("{:?} is outside the grid", position))
+    }
+}
+
+impl<T> IndexMut<Position> for Grid<T> {
+    fn index_mut(&mut self, position: Position) -> &mut T {
+        self.get_mut(position)
+            .unwrap_or_else(|| panic!("{:?} is outside the grid", position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn sample() -> Grid<char> {
+        Grid::parse_chars("abc\ndef").unwrap()
+    }
+
+    fn to_string(grid: &Grid<char>) -> String {
+        (0..grid.height())
+            .map(|row| grid.row(row).collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn should_parse_maps() {
+        let grid = sample();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid.get((1, 2)), Some(&'f'));
+        assert_eq!(grid.get((2, 0)), None);
+        assert_eq!(grid.get((0, 3)), None);
+
+        let digits = Grid::parse_digits("012\n345\n").unwrap();
+        assert_eq!(
+            digits.iter().copied().collect::<Vec<u8>>(),
+            vec![0, 1, 2, 3, 4, 5]
+        );
+        assert_eq!(Grid::parse_digits("").unwrap().width(), 0);
+    }
+
+    #[test]
+    fn should_reject_invalid_maps() {
+        assert_eq!(
+            Grid::parse_digits("12\n3x").unwrap_err().to_string(),
+            "Line 2: invalid cell 'x'"
+        );
+        assert_eq!(
+            Grid::parse_chars("ab\nc").unwrap_err().to_string(),
+            "Line 2: 1 cells, expected 2"
+        );
+        assert!(Grid::new(2, 2, vec![1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn should_iterate_rows_columns_and_rays() {
+        let grid = sample();
+        assert_eq!(grid.row(1).collect::<String>(), "def");
+        assert_eq!(grid.column(1).collect::<String>(), "be");
+        assert_eq!(
+            grid.ray((0, 0), Direction::Right).collect::<Vec<_>>(),
+            vec![(0, 1), (0, 2)]
+        );
+        assert_eq!(
+            grid.ray((1, 2), Direction::Up).collect::<Vec<_>>(),
+            vec![(0, 2)]
+        );
+        assert_eq!(grid.ray((0, 1), Direction::Up).count(), 0);
+        assert_eq!(grid.ray((1, 2), Direction::Left).count(), 2);
+        assert_eq!(
+            grid.ray((1, 0), Direction::UpRight).collect::<Vec<_>>(),
+            vec![(0, 1)]
+        );
+        assert_eq!(grid.ray((0, 0), Direction::DownRight).count(), 1);
+    }
+
+    #[test]
+    fn should_list_neighbours() {
+        let grid = sample();
+        assert_eq!(
+            grid.neighbours((0, 0)).collect::<Vec<_>>(),
+            vec![(1, 0), (0, 1)]
+        );
+        assert_eq!(grid.neighbours((1, 1)).count(), 3);
+        assert_eq!(
+            grid.neighbours8((0, 1)).collect::<Vec<_>>(),
+            vec![(0, 0), (0, 2), (1, 0), (1, 1), (1, 2)]
+        );
+    }
+
+    #[test]
+    fn should_transpose_and_rotate() {
+        let grid = sample();
+        assert_eq!(to_string(&grid.transpose()), "ad\nbe\ncf");
+        assert_eq!(to_string(&grid.rotate()), "da\neb\nfc");
+        assert_eq!(grid.rotate().rotate().rotate().rotate(), grid);
+        assert_eq!(grid.transpose().transpose(), grid);
+    }
+}
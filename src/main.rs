@@ -1,3 +1,4 @@
+mod cli;
 mod day01;
 mod day02;
 mod day03;
@@ -7,6 +8,9 @@ mod day06;
 mod day07;
 mod day08;
 mod day10;
+mod grid;
+mod random;
+mod validation;
 
 #[allow(dead_code)]
 fn previous_days() {
@@ -21,5 +25,19 @@ fn previous_days() {
 }
 
 fn main() {
-    day10::day10().unwrap();
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let result = match args.first().map(String::as_str) {
+        Some("day05") => day05::cli(&args[1..]),
+        Some("day06") => day06::cli(&args[1..]),
+        Some("day07") => day07::cli(&args[1..]),
+        Some("day08") => day08::cli(&args[1..]),
+        Some("day10") => day10::cli(&args[1..]),
+        _ => day10::day10(),
+    };
+
+    // Invalid inputs are reported rather than panicking
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
 }
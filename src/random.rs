@@ -0,0 +1,13 @@
+// Xorshift generator, enough to build reproducible test inputs without a dependency. The
+// seed must not be 0.
+pub struct Random(pub u64);
+
+impl Random {
+    // Value in 0..max
+    pub fn next(&mut self, max: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % max
+    }
+}
@@ -0,0 +1,35 @@
+use anyhow::anyhow;
+use std::fmt;
+
+pub type LineNumber = usize;
+
+// Strict mode stops on the first problem, lenient mode reports it as a warning and keeps going
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Strict,
+    Lenient,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub line: LineNumber,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Mode {
+    pub fn report(&self, warning: Warning, warnings: &mut Vec<Warning>) -> anyhow::Result<()> {
+        match self {
+            Mode::Strict => Err(anyhow!("{}", warning)),
+            Mode::Lenient => {
+                warnings.push(warning);
+                Ok(())
+            }
+        }
+    }
+}